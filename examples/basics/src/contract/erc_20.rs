@@ -511,25 +511,52 @@ pub mod erc_20 {
             ))
         }
         ///
-        /// Deploy the contract
-        /// 'caller' is the deployer
+        /// Deploy the contract with its constructor arguments.
+        /// Send the deployment with `.send(caller)`, where 'caller' is the deployer
         ///
-        pub fn deploy<T: ::ethers_core::abi::Tokenize>(
+        pub fn deploy(
             client: ::std::sync::Arc<M>,
-            caller: ::ethers_core::abi::Address,
-            args: T,
-        ) -> eyre::Result<::ethers_core::abi::Address> {
-            let params = args.into_tokens();
+            name: ::std::string::String,
+            symbol: ::std::string::String,
+            decimals: u8,
+        ) -> ::revmup_contract::eyre::Result<::revmup_contract::deploy::ContractDeployment<M, Self>>
+        {
+            let tokens = ::ethers_core::abi::Tokenize::into_tokens((name, symbol, decimals));
             let abi = ::ethers_core::abi::Abi::from(ERC20_ABI.clone());
-            let encoded = match abi.constructor() {
-                Some(c) => c.encode_input(ERC20_BYTECODE.clone().to_vec().into(), &params)?,
-                _ => ERC20_BYTECODE.clone().to_vec().into(),
+            let init_code = match abi.constructor() {
+                Some(c) => c.encode_input(ERC20_BYTECODE.clone().to_vec(), &tokens)?,
+                _ => ERC20_BYTECODE.clone().to_vec(),
             };
-            let mut tx = ::revm::primitives::TxEnv::default();
-            tx.caller = caller.into();
-            tx.transact_to = ::revm::primitives::TransactTo::create();
-            tx.data = encoded.into();
-            client.deploy(tx)
+            Ok(::revmup_contract::deploy::ContractDeployer::new(
+                client,
+                init_code,
+                |address, client| Self::new(address, client),
+            ))
+        }
+        ///
+        /// Install the contract's deployed bytecode at `address`, without running
+        /// its constructor. Immutables keep the values they had when compiled
+        ///
+        pub fn etch(
+            client: ::std::sync::Arc<M>,
+            address: ::ethers_core::types::Address,
+        ) -> ::revmup_contract::eyre::Result<Self> {
+            Self::etch_with_storage(client, address, ::std::collections::HashMap::new())
+        }
+        ///
+        /// Install the contract's deployed bytecode at `address`, without running
+        /// its constructor, and set the given storage slots
+        ///
+        pub fn etch_with_storage(
+            client: ::std::sync::Arc<M>,
+            address: ::ethers_core::types::Address,
+            storage: ::std::collections::HashMap<
+                ::ethers_core::types::U256,
+                ::ethers_core::types::U256,
+            >,
+        ) -> ::revmup_contract::eyre::Result<Self> {
+            client.etch(address, ERC20_DEPLOYED_BYTECODE.clone(), storage)?;
+            Ok(Self::new(address, client))
         }
         ///Calls the contract's `DOMAIN_SEPARATOR` (0x3644e515) function
         pub fn domain_separator(&self) -> ::revmup_contract::call::ContractCall<M, [u8; 32]> {
@@ -660,6 +687,43 @@ pub mod erc_20 {
                 .method_hash([35, 184, 114, 221], (from, to, amount))
                 .expect("method not found (this should never happen)")
         }
+        ///Gets the contract's `Approval` event
+        pub fn approval_filter(
+            &self,
+        ) -> ::revmup_contract::event::ContractEvent<M, ApprovalFilter> {
+            self.0.event()
+        }
+        ///Gets the contract's `Transfer` event
+        pub fn transfer_filter(
+            &self,
+        ) -> ::revmup_contract::event::ContractEvent<M, TransferFilter> {
+            self.0.event()
+        }
+        ///Calls `callback` with each `Approval` event as it is emitted
+        pub fn on_approval<F>(
+            &self,
+            callback: F,
+        ) -> ::revmup_contract::eyre::Result<::revmup_client::SubscriptionId>
+        where
+            F: Fn(ApprovalFilter, ::ethers_contract::LogMeta) + Send + Sync + 'static,
+        {
+            self.0.event::<ApprovalFilter>().subscribe(callback)
+        }
+        ///Calls `callback` with each `Transfer` event as it is emitted
+        pub fn on_transfer<F>(
+            &self,
+            callback: F,
+        ) -> ::revmup_contract::eyre::Result<::revmup_client::SubscriptionId>
+        where
+            F: Fn(TransferFilter, ::ethers_contract::LogMeta) + Send + Sync + 'static,
+        {
+            self.0.event::<TransferFilter>().subscribe(callback)
+        }
+        /// Returns an `Event` builder for all the events of this contract.
+        pub fn events(&self) -> ::revmup_contract::event::ContractEvent<M, Erc20Events> {
+            self.0
+                .event_with_filter(::core::default::Default::default())
+        }
     }
     impl<M: ::revmup_client::RevmClient> From<::revmup_contract::contract::Contract<M>> for Erc20<M> {
         fn from(contract: ::revmup_contract::contract::Contract<M>) -> Self {
@@ -712,7 +776,7 @@ pub mod erc_20 {
         pub fn get_approval_filter_logs(
             &self,
             logs: Vec<::ethers_core::abi::RawLog>,
-        ) -> eyre::Result<Vec<ApprovalFilter>> {
+        ) -> ::revmup_contract::eyre::Result<Vec<ApprovalFilter>> {
             let e = self.abi().event("Approval")?;
             let results = logs
                 .iter()
@@ -723,7 +787,7 @@ pub mod erc_20 {
                             log.topics.clone(),
                             log.data.clone().into(),
                         )
-                        .map_err(|e| eyre::eyre!("error decoding event: {}", e))
+                        .map_err(|e| ::revmup_contract::eyre::eyre!("error decoding event: {}", e))
                         .ok()
                 })
                 .collect::<Vec<_>>();
@@ -732,7 +796,7 @@ pub mod erc_20 {
         pub fn get_transfer_filter_logs(
             &self,
             logs: Vec<::ethers_core::abi::RawLog>,
-        ) -> eyre::Result<Vec<TransferFilter>> {
+        ) -> ::revmup_contract::eyre::Result<Vec<TransferFilter>> {
             let e = self.abi().event("Transfer")?;
             let results = logs
                 .iter()
@@ -743,13 +807,30 @@ pub mod erc_20 {
                             log.topics.clone(),
                             log.data.clone().into(),
                         )
-                        .map_err(|e| eyre::eyre!("error decoding event: {}", e))
+                        .map_err(|e| ::revmup_contract::eyre::eyre!("error decoding event: {}", e))
                         .ok()
                 })
                 .collect::<Vec<_>>();
             Ok(results)
         }
     }
+    impl ::ethers_contract::EthLogDecode for Erc20Events {
+        fn decode_log(
+            log: &::ethers_core::abi::RawLog,
+        ) -> ::core::result::Result<Self, ::ethers_core::abi::Error> {
+            if let Ok(decoded) =
+                <ApprovalFilter as ::ethers_contract::EthLogDecode>::decode_log(log)
+            {
+                return Ok(Erc20Events::ApprovalFilter(decoded));
+            }
+            if let Ok(decoded) =
+                <TransferFilter as ::ethers_contract::EthLogDecode>::decode_log(log)
+            {
+                return Ok(Erc20Events::TransferFilter(decoded));
+            }
+            Err(::ethers_core::abi::Error::InvalidData)
+        }
+    }
     impl ::core::fmt::Display for Erc20Events {
         fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
             match self {
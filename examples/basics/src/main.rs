@@ -1,6 +1,9 @@
-use ethers_core::utils::parse_ether;
-use revmup_client::BasicClient;
-use std::sync::Arc;
+use ethers_core::{
+    types::{Address, U256},
+    utils::{keccak256, parse_ether},
+};
+use revmup_client::{BasicClient, DETERMINISTIC_DEPLOYER};
+use std::{collections::HashMap, sync::Arc};
 
 mod contract;
 use contract::erc_20::Erc20;
@@ -9,7 +12,7 @@ use contract::erc_20::Erc20;
 /// `revmup -i ./examples/basics/abi -o ./examples/basics/src/contract`
 fn main() {
     // create the client
-    let client = Arc::new(BasicClient::new());
+    let client = Arc::new(BasicClient::builder().create2_deployer(true).build());
 
     // amount to fund accounts
     let amt = parse_ether(3u8).unwrap();
@@ -22,35 +25,39 @@ fn main() {
     let alice = accounts[0];
     let bob = accounts[1];
 
-    // Deploy the ERC20 contract
-    // bob is the deployer...
-    // and the constructor takes 3 args.
-    let addy = Erc20::deploy::<(String, String, u8)>(
-        client.clone(),
-        bob,
-        ("hello".into(), "H".into(), 8u8),
-    )
-    .unwrap();
-    println!("contract address: {}", addy);
-
-    // Create an instance pointing to the contract deployed (via addy)
-    let erc = Erc20::new(addy, client.clone());
+    // Deploy the ERC20 contract with its typed constructor arguments.
+    // bob is the deployer, and the deployment returns the bound contract
+    let (erc, receipt) = Erc20::deploy(client.clone(), "hello".into(), "H".into(), 8u8)
+        .unwrap()
+        .send(bob)
+        .unwrap();
+    println!(
+        "contract address: {} (gas used: {})",
+        erc.address(),
+        receipt.gas_used.unwrap_or_default()
+    );
 
     // Make a read-only call
     let v = erc.name().call().unwrap();
     println!("name: {}", v);
 
+    // Print each transfer as it happens
+    erc.on_transfer(|transfer, meta| {
+        println!("on transfer: {} in block {}", transfer, meta.block_number)
+    })
+    .expect("subscribe");
+
     // Send a tx minting bob 2 tokens
     erc.mint(bob, 2u8.into()).send_transaction(bob).unwrap();
     // note caller (from) is bob ---------------^
 
-    // Check bob's balance
-    let b = erc.balance_of(bob).call().unwrap();
+    // Check bob's balance, calling as alice
+    let b = erc.balance_of(bob).from(alice).call().unwrap();
     println!("bal: {:?}", b);
 
     // view logs
     let (_, logs) = erc
-        .transfer(alice.into(), 1u8.into())
+        .transfer(alice, 1u8.into())
         .send_transaction(bob)
         .unwrap();
 
@@ -63,12 +70,50 @@ fn main() {
     let alogs = erc.get_approval_filter_logs(logs).expect("parse log");
     println!("events: {:?}", alogs);
 
+    // Query the client's log history for transfers to alice
+    let transfers = erc
+        .transfer_filter()
+        .topic2(alice)
+        .from_block(0)
+        .query()
+        .expect("query transfers");
+    for (transfer, meta) in transfers {
+        println!("transfer: {} in block {}", transfer, meta.block_number);
+    }
+
+    // ...or for all of the contract's events
+    let events = erc.events().from_block(0).query().expect("query events");
+    println!("all events: {}", events.len());
+
     // Check erc token balances
     let u = erc.balance_of(bob).call().unwrap();
-    let alicebal = erc.balance_of(alice.into()).call().unwrap();
+    let alicebal = erc.balance_of(alice).call().unwrap();
     println!("bob's bal: {:?}", u);
     println!("alice's bal: {:?}", alicebal);
 
     // Check alice's eth balance
-    println!("eth bal for alice: {:}", client.get_balance(alice))
+    println!("eth bal for alice: {:}", client.get_balance(alice));
+
+    // Deploy a second token at a deterministic address, through the CREATE2 factory
+    let deployment = Erc20::deploy(client.clone(), "det".into(), "D".into(), 8u8)
+        .unwrap()
+        .salt([1u8; 32])
+        .factory(DETERMINISTIC_DEPLOYER);
+    let predicted = deployment.address(alice).expect("salted deployment");
+    let (det, _) = deployment.send(alice).unwrap();
+    assert_eq!(det.address(), predicted);
+    println!("deterministic deployment: {}", det.address());
+
+    // Install the token's runtime code at a fixed address without running its
+    // constructor, giving bob 5 tokens: balanceOf is the mapping at slot 3
+    let fixed: Address = "0x4200000000000000000000000000000000000042"
+        .parse()
+        .unwrap();
+    let mut key = [0u8; 64];
+    key[12..32].copy_from_slice(bob.as_bytes());
+    key[63] = 3;
+    let mut storage = HashMap::new();
+    storage.insert(U256::from(keccak256(key)), U256::from(5));
+    let etched = Erc20::etch_with_storage(client.clone(), fixed, storage).unwrap();
+    println!("etched bal: {}", etched.balance_of(bob).call().unwrap());
 }
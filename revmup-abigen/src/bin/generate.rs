@@ -2,7 +2,7 @@
 /// Generate contracts from json artifacts and output as a module.
 /// We'll add support for crate generation, once revmup is published to crates.io
 ///
-use clap::{Parser, ValueHint};
+use clap::{Parser, ValueEnum, ValueHint};
 use std::path::PathBuf;
 
 use revmup_abigen::multi::MultiAbigen;
@@ -34,15 +34,38 @@ pub struct BindArgs {
         value_name = "PATH"
     )]
     pub output_path: PathBuf,
+
+    /// Layout of the input artifacts
+    #[clap(long, value_enum, default_value_t = Format::Json)]
+    pub format: Format,
     // Generate the bindings as a module
     //#[clap(long)]
     //module: bool,
 }
 
+/// How the input path is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Every json file is a contract, named after the file. Reads Hardhat `artifacts`
+    /// and Truffle `build` directories
+    Json,
+    /// A Foundry `out` directory, without tests, scripts and forge-std
+    Foundry,
+    /// A `solc --standard-json` output file
+    StandardJson,
+    /// A `solc --combined-json abi,bin,bin-runtime` output file
+    CombinedJson,
+}
+
 fn main() -> eyre::Result<()> {
     let args = BindArgs::parse();
 
-    let gen = MultiAbigen::from_json_files(args.input_path)?;
+    let gen = match args.format {
+        Format::Json => MultiAbigen::from_json_files(args.input_path)?,
+        Format::Foundry => MultiAbigen::from_foundry_out(args.input_path)?,
+        Format::StandardJson => MultiAbigen::from_solc_standard_json(args.input_path)?,
+        Format::CombinedJson => MultiAbigen::from_solc_combined_json(args.input_path)?,
+    };
     let total = gen.len();
     println!("... generating {:} contract(s)", total);
     let bindings = gen.build()?;
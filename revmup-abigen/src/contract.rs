@@ -18,6 +18,7 @@ use ethers_core::{
 use eyre::{eyre, Context as _, Result};
 use proc_macro2::{Ident, Literal, TokenStream};
 use quote::{format_ident, quote};
+use revmup_client::link::{LinkReferences, UnlinkedBytecode};
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use syn::Path;
@@ -128,6 +129,9 @@ pub struct Context {
 
     /// Deployed bytecode extracted from the abi string input, if present.
     contract_deployed_bytecode: Option<Bytes>,
+
+    /// Library placeholders in the bytecode, zeroed in `contract_bytecode`.
+    contract_link_references: LinkReferences,
 }
 
 impl Context {
@@ -143,15 +147,17 @@ impl Context {
         // 2. Declare events structs & impl FromTokens for each event
         let events_decl = self.events_declaration()?;
 
-        // @note REMOVE/UPDATE WITH LOGIC
         // 3. impl block for the event functions
-        //let contract_events = self.event_methods()?;
+        let contract_events = self.event_methods()?;
 
         // 4. impl block for the contract methods and their corresponding types
         let (contract_methods, call_structs) = self.methods_and_call_structs()?;
 
         // 5. The deploy method, only if the contract has a bytecode object
-        let deployment_methods = self.deployment_methods();
+        let deployment_methods = self.deployment_methods()?;
+
+        // 5b. The etch methods, only if the contract has a deployed bytecode object
+        let etch_methods = self.etch_methods();
 
         // 6. Declare the structs parsed from the human readable abi
         let abi_structs_decl = self.abi_structs()?;
@@ -178,9 +184,11 @@ impl Context {
 
                     #deployment_methods
 
+                    #etch_methods
+
                     #contract_methods
 
-                    //#contract_events
+                    #contract_events
                 }
 
                 impl<M: ::revmup_client::RevmClient> From<::revmup_contract::contract::Contract<M>> for #name<M> {
@@ -215,6 +223,9 @@ impl Context {
         // holds the deployed bytecode parsed from the abi_str, if present
         let mut contract_deployed_bytecode = None;
 
+        // holds the library placeholders of the bytecode, if it needs linking
+        let mut contract_link_references = LinkReferences::new();
+
         let (abi, human_readable, abi_parser) = parse_abi(&abi_str).wrap_err_with(|| {
             eyre::eyre!("error parsing abi for contract: {}", args.contract_name)
         })?;
@@ -243,6 +254,17 @@ impl Context {
                 JsonAbi::Object(obj) => {
                     contract_bytecode = obj.bytecode;
                     contract_deployed_bytecode = obj.deployed_bytecode;
+                    // bytecode with library placeholders isn't valid hex until linked
+                    if contract_bytecode.is_none() {
+                        let unlinked =
+                            UnlinkedBytecode::from_artifact(&abi_str).wrap_err_with(|| {
+                                eyre!("error reading bytecode of contract: {}", args.contract_name)
+                            })?;
+                        if let Some(unlinked) = unlinked {
+                            contract_bytecode = Some(unlinked.bytecode);
+                            contract_link_references = unlinked.link_references;
+                        }
+                    }
                     InternalStructs::new(obj.abi)
                 }
                 JsonAbi::Array(abi) => InternalStructs::new(abi),
@@ -305,6 +327,7 @@ impl Context {
             contract_ident: args.contract_name,
             contract_bytecode,
             contract_deployed_bytecode,
+            contract_link_references,
             method_aliases,
             error_aliases: Default::default(),
             event_aliases,
@@ -332,6 +355,11 @@ impl Context {
         format_ident!("{}_DEPLOYED_BYTECODE", self.contract_name.to_uppercase())
     }
 
+    /// Name of the `Lazy` that stores the link references of the Bytecode.
+    pub(crate) fn inline_link_references_ident(&self) -> Ident {
+        format_ident!("{}_LINK_REFERENCES", self.contract_name.to_uppercase())
+    }
+
     /// Returns a reference to the internal ABI struct mapping table.
     pub fn internal_structs(&self) -> &InternalStructs {
         &self.internal_structs
@@ -402,6 +430,18 @@ impl Context {
             }
         });
 
+        let link_references = (!self.contract_link_references.is_empty()).then(|| {
+            let link_references_name = self.inline_link_references_ident();
+            let references = self.contract_link_references.iter().map(|(name, offsets)| {
+                quote! { (#name.to_string(), vec![#( #offsets ),*]) }
+            });
+            quote! {
+                /// The offsets of the library placeholders in the bytecode, by library.
+                pub static #link_references_name: #ethers_contract::Lazy<::revmup_client::link::LinkReferences> =
+                    #ethers_contract::Lazy::new(|| ::revmup_client::link::LinkReferences::from([#( #references ),*]));
+            }
+        });
+
         quote! {
             // The `Lazy` ABI
             #abi
@@ -409,6 +449,9 @@ impl Context {
             // The static Bytecode, if present
             #bytecode
 
+            // The library placeholders of the Bytecode, if it needs linking
+            #link_references
+
             // The static deployed Bytecode, if present
             #deployed_bytecode
 
@@ -14,6 +14,31 @@ use quote::quote;
 use std::collections::BTreeMap;
 
 impl Context {
+    /// Expands the event filter methods of the contract
+    pub(crate) fn event_methods(&self) -> Result<TokenStream> {
+        let sorted_events: BTreeMap<_, _> = self.abi.events.iter().collect();
+        let filter_methods = sorted_events
+            .values()
+            .flat_map(std::ops::Deref::deref)
+            .map(|event| self.expand_filter(event))
+            .collect::<Vec<_>>();
+        let subscription_methods = sorted_events
+            .values()
+            .flat_map(std::ops::Deref::deref)
+            .map(|event| self.expand_subscription(event))
+            .collect::<Vec<_>>();
+
+        let events_method = self.expand_events_method();
+
+        Ok(quote! {
+            #( #filter_methods )*
+
+            #( #subscription_methods )*
+
+            #events_method
+        })
+    }
+
     /// Expands each event to a struct + its impl Detokenize block
     pub fn events_declaration(&self) -> Result<TokenStream> {
         let sorted_events: BTreeMap<_, _> = self.abi.events.clone().into_iter().collect();
@@ -93,7 +118,7 @@ impl Context {
 
             impl<M: ::revmup_client::RevmClient>#name<M> {
                 #(
-                    pub fn #event_method_names(&self, logs: Vec<::ethers_core::abi::RawLog>) -> eyre::Result<Vec<#variants>> {
+                    pub fn #event_method_names(&self, logs: Vec<::ethers_core::abi::RawLog>) -> ::revmup_contract::eyre::Result<Vec<#variants>> {
                         let e = self.abi().event(#quoted_names_str)?;
                         let results = logs.iter()
                             .flat_map(|log| {
@@ -101,13 +126,24 @@ impl Context {
                                     &e.name,
                                     log.topics.clone(),
                                     log.data.clone().into()
-                                ).map_err(|e| eyre::eyre!("error decoding event: {}", e)).ok()
+                                ).map_err(|e| ::revmup_contract::eyre::eyre!("error decoding event: {}", e)).ok()
                         }).collect::<Vec<_>>();
                         Ok(results)
                     }
                 )*
             }
 
+            impl #ethers_contract::EthLogDecode for #enum_name {
+                fn decode_log(log: &::ethers_core::abi::RawLog) -> ::core::result::Result<Self, ::ethers_core::abi::Error> {
+                    #(
+                        if let Ok(decoded) = <#variants as #ethers_contract::EthLogDecode>::decode_log(log) {
+                            return Ok(#enum_name::#variants(decoded))
+                        }
+                    )*
+                    Err(::ethers_core::abi::Error::InvalidData)
+                }
+            }
+
             impl ::core::fmt::Display for #enum_name {
                 fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
                     match self {
@@ -134,12 +170,10 @@ impl Context {
     }
 
     /// Expands the `events` function that bundles all declared events of this contract
-    /// @todo REMOVE?
     fn expand_events_method(&self) -> Option<TokenStream> {
         let sorted_events: BTreeMap<_, _> = self.abi.events.clone().into_iter().collect();
 
         let mut iter = sorted_events.values().flatten();
-        let ethers_contract = ethers_contract_crate();
 
         if let Some(event) = iter.next() {
             let ty = if iter.next().is_some() {
@@ -153,11 +187,7 @@ impl Context {
 
             Some(quote! {
                 /// Returns an `Event` builder for all the events of this contract.
-                pub fn events(&self) -> #ethers_contract::builders::Event<
-                    ::std::sync::Arc<M>,
-                    M,
-                    #ty,
-                > {
+                pub fn events(&self) -> ::revmup_contract::event::ContractEvent<M, #ty> {
                     self.0.event_with_filter(::core::default::Default::default())
                 }
             })
@@ -166,8 +196,7 @@ impl Context {
         }
     }
 
-    /// Expands into a single method for contracting an event stream.
-    /// @todo REMOVE?
+    /// Expands into a single method for querying an event from the client's logs.
     fn expand_filter(&self, event: &Event) -> TokenStream {
         let name = &event.name;
         let sig = event.abi_signature();
@@ -186,16 +215,42 @@ impl Context {
 
         let doc_str = format!("Gets the contract's `{name}` event");
 
+        quote! {
+            #[doc = #doc_str]
+            pub fn #function_name(&self) -> ::revmup_contract::event::ContractEvent<M, #struct_name> {
+                self.0.event()
+            }
+        }
+    }
+
+    /// Expands into a single method for subscribing to an event as transactions are
+    /// committed.
+    fn expand_subscription(&self, event: &Event) -> TokenStream {
+        let name = &event.name;
+        let sig = event.abi_signature();
+        let alias = self.event_aliases.get(&sig).cloned();
+
+        let function_name = {
+            let name = if let Some(ref id) = alias {
+                id.to_string().to_snake_case()
+            } else {
+                name.to_snake_case()
+            };
+            util::safe_ident(&format!("on_{name}"))
+        };
+        let struct_name = event_struct_name(name, alias);
+
+        let doc_str = format!("Calls `callback` with each `{name}` event as it is emitted");
+
         let ethers_contract = ethers_contract_crate();
 
         quote! {
             #[doc = #doc_str]
-            pub fn #function_name(&self) -> #ethers_contract::builders::Event<
-                ::std::sync::Arc<M>,
-                M,
-                #struct_name
-            > {
-                self.0.event()
+            pub fn #function_name<F>(&self, callback: F) -> ::revmup_contract::eyre::Result<::revmup_client::SubscriptionId>
+            where
+                F: Fn(#struct_name, #ethers_contract::LogMeta) + Send + Sync + 'static,
+            {
+                self.0.event::<#struct_name>().subscribe(callback)
             }
         }
     }
@@ -295,7 +350,7 @@ mod tests {
             #[doc = "Gets the contract's `Transfer` event"]
             pub fn transfer_event_filter(
                 &self
-            ) -> ::ethers_contract::builders::Event<::std::sync::Arc<M>, M, TransferEventFilter>
+            ) -> ::revmup_contract::event::ContractEvent<M, TransferEventFilter>
             {
                 self.0.event()
             }
@@ -331,13 +386,52 @@ mod tests {
             #[doc = "Gets the contract's `Transfer` event"]
             pub fn transfer_filter(
                 &self
-            ) -> ::ethers_contract::builders::Event<::std::sync::Arc<M>, M, TransferFilter>
+            ) -> ::revmup_contract::event::ContractEvent<M, TransferFilter>
             {
                 self.0.event()
             }
         });
     }
 
+    #[test]
+    fn expand_transfer_subscription() {
+        let event = Event {
+            name: "Transfer".into(),
+            inputs: vec![
+                EventParam {
+                    name: "from".into(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                EventParam {
+                    name: "to".into(),
+                    kind: ParamType::Address,
+                    indexed: true,
+                },
+                EventParam {
+                    name: "amount".into(),
+                    kind: ParamType::Uint(256),
+                    indexed: false,
+                },
+            ],
+            anonymous: false,
+        };
+        let cx = test_context();
+        #[rustfmt::skip]
+        assert_quote!(cx.expand_subscription(&event), {
+            #[doc = "Calls `callback` with each `Transfer` event as it is emitted"]
+            pub fn on_transfer<F>(
+                &self,
+                callback: F
+            ) -> ::revmup_contract::eyre::Result<::revmup_client::SubscriptionId>
+            where
+                F: Fn(TransferFilter, ::ethers_contract::LogMeta) + Send + Sync + 'static,
+            {
+                self.0.event::<TransferFilter>().subscribe(callback)
+            }
+        });
+    }
+
     #[test]
     fn expand_data_struct_value() {
         let event = Event {
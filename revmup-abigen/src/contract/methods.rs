@@ -50,11 +50,11 @@ impl Context {
     }
 
     /// Returns all deploy (constructor) implementations
-    pub(crate) fn deployment_methods(&self) -> Option<TokenStream> {
+    pub(crate) fn deployment_methods(&self) -> Result<Option<TokenStream>> {
         // don't generate deploy if no bytecode
-        self.contract_bytecode.as_ref()?;
-
-        let ethers_core = ethers_core_crate();
+        if self.contract_bytecode.is_none() {
+            return Ok(None);
+        }
 
         let abi_name = self.inline_abi_ident();
         let get_abi = quote! {
@@ -66,29 +66,116 @@ impl Context {
             #bytecode_name.clone()
         };
 
+        // libraries the bytecode links to are deployed separately, and their
+        // addresses patched in
+        let libraries = self
+            .contract_link_references
+            .keys()
+            .map(|name| (name, util::safe_ident(&name.to_snake_case())))
+            .collect::<Vec<_>>();
+        let library_params = libraries
+            .iter()
+            .map(|(_, param)| quote! { , #param: ::ethers_core::types::Address });
+        let doc = if libraries.is_empty() {
+            vec![
+                "",
+                " Deploy the contract with its constructor arguments.",
+                " Send the deployment with `.send(caller)`, where 'caller' is the deployer",
+                "",
+            ]
+        } else {
+            vec![
+                "",
+                " Deploy the contract with the addresses of the libraries it links to, and its",
+                " constructor arguments.",
+                " Send the deployment with `.send(caller)`, where 'caller' is the deployer",
+                "",
+            ]
+        };
+        let (link, get_bytecode) = if libraries.is_empty() {
+            (quote!(), get_bytecode)
+        } else {
+            let link_references_name = self.inline_link_references_ident();
+            let entries = libraries
+                .iter()
+                .map(|(name, param)| quote! { (#name.to_string(), #param) });
+            let link = quote! {
+                let libraries = ::std::collections::HashMap::from([#( #entries ),*]);
+                let bytecode = ::revmup_client::link::link_bytecode(
+                    &#bytecode_name,
+                    &#link_references_name,
+                    &libraries,
+                )?;
+            };
+            (link, quote!(bytecode))
+        };
+
+        // constructor structs aren't named in the ABI, so they expand as tuples
+        let inputs = self
+            .abi
+            .constructor
+            .as_ref()
+            .map(|constructor| constructor.inputs.as_slice())
+            .unwrap_or_default();
+        let params = types::expand_params(inputs, |_| None)?
+            .into_iter()
+            .map(|(name, ty)| quote! { #name: #ty });
+        let params = quote! { #( #library_params )* #( , #params )* };
+        let args = expand_call_args(inputs);
+
+        Ok(Some(quote! {
+            #( #[doc = #doc] )*
+            pub fn deploy(
+                client: ::std::sync::Arc<M> #params
+            ) -> ::revmup_contract::eyre::Result<::revmup_contract::deploy::ContractDeployment<M, Self>> {
+                #link
+                let tokens = ::ethers_core::abi::Tokenize::into_tokens(#args);
+                let abi = ::ethers_core::abi::Abi::from(#get_abi);
+                let init_code = match abi.constructor() {
+                    Some(c) => c.encode_input(#get_bytecode.to_vec(), &tokens)?,
+                    _ => #get_bytecode.to_vec(),
+                };
+
+                Ok(::revmup_contract::deploy::ContractDeployer::new(
+                    client,
+                    init_code,
+                    |address, client| Self::new(address, client),
+                ))
+            }
+        }))
+    }
+
+    /// Returns the etch implementations, installing the deployed bytecode without
+    /// running the constructor
+    pub(crate) fn etch_methods(&self) -> Option<TokenStream> {
+        // don't generate etch if no deployed bytecode
+        self.contract_deployed_bytecode.as_ref()?;
+
+        let deployed_bytecode_name = self.inline_deployed_bytecode_ident();
+
         Some(quote! {
             ///
-            /// Deploy the contract
-            /// 'caller' is the deployer
+            /// Install the contract's deployed bytecode at `address`, without running
+            /// its constructor. Immutables keep the values they had when compiled
             ///
-            pub fn deploy<T: #ethers_core::abi::Tokenize>(
+            pub fn etch(
                 client: ::std::sync::Arc<M>,
-                caller: ::ethers_core::abi::Address,
-                args: T
-            ) -> eyre::Result<::ethers_core::abi::Address> {
-                let params = args.into_tokens();
-                let abi = ::ethers_core::abi::Abi::from(#get_abi);
-                let encoded = match abi.constructor() {
-                    Some(c) => c.encode_input(#get_bytecode.to_vec().into(), &params)?,
-                    _ => #get_bytecode.to_vec().into(),
-                };
-
-                let mut tx = ::revm::primitives::TxEnv::default();
-                tx.caller = caller.into();
-                tx.transact_to = ::revm::primitives::TransactTo::create();
-                tx.data = encoded.into();
+                address: ::ethers_core::types::Address,
+            ) -> ::revmup_contract::eyre::Result<Self> {
+                Self::etch_with_storage(client, address, ::std::collections::HashMap::new())
+            }
 
-                client.deploy(tx)
+            ///
+            /// Install the contract's deployed bytecode at `address`, without running
+            /// its constructor, and set the given storage slots
+            ///
+            pub fn etch_with_storage(
+                client: ::std::sync::Arc<M>,
+                address: ::ethers_core::types::Address,
+                storage: ::std::collections::HashMap<::ethers_core::types::U256, ::ethers_core::types::U256>,
+            ) -> ::revmup_contract::eyre::Result<Self> {
+                client.etch(address, #deployed_bytecode_name.clone(), storage)?;
+                Ok(Self::new(address, client))
             }
         })
     }
@@ -294,29 +381,7 @@ impl Context {
 
     /// Expands the arguments for the call that eventually calls the contract
     fn expand_contract_call_args(&self, fun: &Function) -> TokenStream {
-        let mut call_args = fun.inputs.iter().enumerate().map(|(idx, param)| {
-            let name = util::expand_input_name(idx, &param.name);
-            match param.kind {
-                // this is awkward edge case where the function inputs are a single struct
-                // we need to force this argument into a tuple so it gets expanded to
-                // `((#name,))` this is currently necessary because
-                // internally `flatten_tokens` is called which removes the
-                // outermost `tuple` level and since `((#name))` is not
-                // a rust tuple it doesn't get wrapped into another tuple that will be peeled
-                // off by `flatten_tokens`
-                ParamType::Tuple(_) if fun.inputs.len() == 1 => {
-                    // make sure the tuple gets converted to `Token::Tuple`
-                    quote!((#name,))
-                }
-                _ => name,
-            }
-        });
-
-        match fun.inputs.len() {
-            0 => quote!(()),
-            1 => call_args.next().unwrap(),
-            _ => quote!(( #( #call_args ),* )),
-        }
+        expand_call_args(&fun.inputs)
     }
 
     /// Expands a single function with the given alias
@@ -403,7 +468,7 @@ impl Context {
             let num_functions = functions.len();
             // sort functions by number of inputs asc
             let mut functions = functions.iter().enumerate().collect::<Vec<_>>();
-            functions.sort_by(|(_, f1), (_, f2)| f1.inputs.len().cmp(&f2.inputs.len()));
+            functions.sort_by_key(|(_, f)| f.inputs.len());
 
             // the `functions` are now mapped with their index as defined in the ABI, but
             // we always want the zero arg function (`log()`) to be `log0`
@@ -587,6 +652,33 @@ impl Context {
     }
 }
 
+/// Expands the arguments for a call of a function or constructor taking `inputs`
+fn expand_call_args(inputs: &[Param]) -> TokenStream {
+    let mut call_args = inputs.iter().enumerate().map(|(idx, param)| {
+        let name = util::expand_input_name(idx, &param.name);
+        match param.kind {
+            // this is awkward edge case where the function inputs are a single struct
+            // we need to force this argument into a tuple so it gets expanded to
+            // `((#name,))` this is currently necessary because
+            // internally `flatten_tokens` is called which removes the
+            // outermost `tuple` level and since `((#name))` is not
+            // a rust tuple it doesn't get wrapped into another tuple that will be peeled
+            // off by `flatten_tokens`
+            ParamType::Tuple(_) if inputs.len() == 1 => {
+                // make sure the tuple gets converted to `Token::Tuple`
+                quote!((#name,))
+            }
+            _ => name,
+        }
+    });
+
+    match inputs.len() {
+        0 => quote!(()),
+        1 => call_args.next().unwrap(),
+        _ => quote!(( #( #call_args ),* )),
+    }
+}
+
 fn expand_selector(selector: Selector) -> TokenStream {
     let bytes = selector.iter().copied().map(Literal::u8_unsuffixed);
     quote!([ #( #bytes ),* ])
@@ -829,4 +921,30 @@ mod tests {
             { (bool, ::ethers_core::types::Address) },
         );
     }
+
+    #[test]
+    fn deploys_with_library_addresses() {
+        let artifact = r#"{
+            "abi": [{"type": "constructor", "inputs": [{"name": "x", "type": "uint256"}], "stateMutability": "nonpayable"}],
+            "bytecode": "0x6073__$a1b2c3d4e5f60718293a4b5c6d7e8f9012$__00",
+            "linkReferences": {"contracts/MathLib.sol": {"MathLib": [{"start": 2, "length": 20}]}}
+        }"#;
+        let abigen = crate::Abigen::new("Linked", artifact).unwrap();
+        let ctx = Context::from_abigen(abigen).unwrap();
+        assert_eq!(
+            ctx.contract_bytecode.as_deref(),
+            Some(&[[0x60, 0x73].as_slice(), &[0; 20], &[0]].concat()[..])
+        );
+
+        let deploy = ctx.deployment_methods().unwrap().unwrap().to_string();
+        let params = quote! {
+            client: ::std::sync::Arc<M>, math_lib: ::ethers_core::types::Address, x: ::ethers_core::types::U256
+        };
+        assert!(deploy.contains(&params.to_string()), "{deploy}");
+        assert!(deploy.contains("link_bytecode"));
+        assert!(ctx
+            .struct_declaration()
+            .to_string()
+            .contains("LINKED_LINK_REFERENCES"));
+    }
 }
@@ -0,0 +1,199 @@
+//! Loading contracts from a Foundry `out/` directory
+
+use crate::{
+    source::artifacts::{is_artifact_file, unique_names, Artifact},
+    util, Abigen, MultiAbigen, Source,
+};
+use eyre::Result;
+use std::path::{Path, PathBuf};
+
+/// Loads the artifacts Foundry writes to `out/<Source>.sol/<Contract>.json`.
+///
+/// Build info, test (`*.t.sol`) and script (`*.s.sol`) contracts and forge-std are
+/// skipped by default. Contracts with the same name in different sources are named
+/// after the directories of their sources, from the closest one until the names are
+/// unique: `src/v1/Token.sol` and `src/v2/Token.sol` give `V1Token` and `V2Token`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use revmup_abigen::FoundryArtifacts;
+/// let gen = FoundryArtifacts::new("./out").load().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FoundryArtifacts {
+    root: PathBuf,
+    tests: bool,
+    scripts: bool,
+    forge_std: bool,
+}
+
+impl FoundryArtifacts {
+    /// Load the artifacts in `root`, Foundry's `out` directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            tests: false,
+            scripts: false,
+            forge_std: false,
+        }
+    }
+
+    /// Whether to include contracts from `*.t.sol` files
+    #[must_use]
+    pub fn tests(mut self, include: bool) -> Self {
+        self.tests = include;
+        self
+    }
+
+    /// Whether to include contracts from `*.s.sol` files
+    #[must_use]
+    pub fn scripts(mut self, include: bool) -> Self {
+        self.scripts = include;
+        self
+    }
+
+    /// Whether to include the contracts of forge-std
+    #[must_use]
+    pub fn forge_std(mut self, include: bool) -> Self {
+        self.forge_std = include;
+        self
+    }
+
+    /// Read the artifacts into an `Abigen` per contract
+    pub fn load(&self) -> Result<MultiAbigen> {
+        let mut artifacts = Vec::new();
+        for path in util::json_files(&self.root) {
+            if !is_artifact_file(&path) {
+                continue;
+            }
+            if let Some(artifact) = self.read(path)? {
+                artifacts.push(artifact);
+            }
+        }
+        eyre::ensure!(
+            !artifacts.is_empty(),
+            "No Foundry artifacts found in directory: {}",
+            self.root.display()
+        );
+
+        let names = unique_names(&artifacts);
+        artifacts
+            .into_iter()
+            .zip(names)
+            .map(|(artifact, name)| {
+                let path = artifact.path.to_str().ok_or_else(|| {
+                    eyre::eyre!("path is not valid UTF-8: {}", artifact.path.display())
+                })?;
+                Ok(Abigen::new_raw(
+                    syn::parse_str(&name)?,
+                    Source::local(path)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Read the artifact at `path`, unless it should be skipped
+    fn read(&self, path: PathBuf) -> Result<Option<Artifact>> {
+        let mut artifact = Artifact::read(path)?;
+        if !artifact.has_abi {
+            return Ok(None);
+        }
+        // without metadata, the artifact is in a directory named after its source
+        if artifact.source.is_none() {
+            artifact.source = artifact
+                .path
+                .parent()
+                .and_then(Path::file_name)
+                .map(PathBuf::from);
+        }
+
+        let source = artifact.source.as_deref().unwrap_or(Path::new(""));
+        let file = source
+            .file_name()
+            .and_then(|file| file.to_str())
+            .unwrap_or_default();
+        let skipped = (!self.tests && file.ends_with(".t.sol"))
+            || (!self.scripts && file.ends_with(".s.sol"))
+            || (!self.forge_std && source.components().any(|c| c.as_os_str() == "forge-std"));
+        Ok((!skipped).then_some(artifact))
+    }
+}
+
+impl MultiAbigen {
+    /// Reads the artifacts of a Foundry `out` directory, skipping tests, scripts
+    /// and forge-std. See [`FoundryArtifacts`] to include them.
+    pub fn from_foundry_out(root: impl Into<PathBuf>) -> Result<Self> {
+        FoundryArtifacts::new(root).load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn artifact(source: &str, name: &str) -> String {
+        format!(
+            r#"{{
+                "abi": [],
+                "bytecode": {{ "object": "0x6000", "sourceMap": "", "linkReferences": {{}} }},
+                "deployedBytecode": {{ "object": "0x00", "sourceMap": "", "linkReferences": {{}} }},
+                "methodIdentifiers": {{}},
+                "metadata": {{
+                    "compiler": {{ "version": "0.8.21" }},
+                    "settings": {{ "compilationTarget": {{ "{source}": "{name}" }} }}
+                }}
+            }}"#
+        )
+    }
+
+    fn write(out: &Path, dir: &str, source: &str, name: &str) {
+        let dir = out.join(dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{name}.json")), artifact(source, name)).unwrap();
+    }
+
+    fn names(gen: &MultiAbigen) -> Vec<String> {
+        let mut names: Vec<_> = gen.iter().map(|g| g.contract_name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn loads_foundry_out() {
+        let out = tempfile::tempdir().unwrap();
+        let out = out.path();
+        write(out, "Counter.sol", "src/Counter.sol", "Counter");
+        write(out, "Counter.t.sol", "test/Counter.t.sol", "CounterTest");
+        write(
+            out,
+            "Counter.s.sol",
+            "script/Counter.s.sol",
+            "CounterScript",
+        );
+        write(out, "Test.sol", "lib/forge-std/src/Test.sol", "Test");
+        write(out, "v1/Token.sol", "src/v1/Token.sol", "Token");
+        write(out, "v2/Token.sol", "src/v2/Token.sol", "Token");
+        write(out, "Tokens.sol", "src/Tokens.sol", "Token");
+        fs::create_dir_all(out.join("build-info")).unwrap();
+        fs::write(out.join("build-info/abc.json"), r#"{"id": "abc"}"#).unwrap();
+
+        let gen = MultiAbigen::from_foundry_out(out).unwrap();
+        assert_eq!(
+            names(&gen),
+            ["Counter", "TokensToken", "V1Token", "V2Token"]
+        );
+
+        let gen = FoundryArtifacts::new(out)
+            .tests(true)
+            .scripts(true)
+            .forge_std(true)
+            .load()
+            .unwrap();
+        assert_eq!(gen.len(), 7);
+
+        // every binding generates
+        MultiAbigen::from_foundry_out(out).unwrap().build().unwrap();
+    }
+}
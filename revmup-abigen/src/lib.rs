@@ -23,9 +23,15 @@ pub use contract::structs::InternalStructs;
 pub mod filter;
 pub use filter::{ContractFilter, ExcludeContracts, SelectContracts};
 
+pub mod foundry;
+pub use foundry::FoundryArtifacts;
+
 pub mod multi;
 pub use multi::MultiAbigen;
 
+pub mod solc;
+pub use solc::SolcContract;
+
 mod source;
 mod util;
 mod verbatim;
@@ -1,5 +1,8 @@
 //! Generate bindings for multiple `Abigen`
-use crate::{util, Abigen, Context, ContractBindings, ContractFilter, ExpandedContract};
+use crate::{
+    source::artifacts::{self, Artifact},
+    util, Abigen, Context, ContractBindings, ContractFilter, ExpandedContract,
+};
 use eyre::Result;
 use inflector::Inflector;
 use proc_macro2::TokenStream;
@@ -77,6 +80,10 @@ impl MultiAbigen {
     /// `ContractBindings`.
     /// This is equivalent to calling `MultiAbigen::new` with all the json files and their filename.
     ///
+    /// Hardhat `artifacts` and Truffle `build` directories can be read directly: Hardhat's
+    /// `.dbg.json` and `build-info` files are skipped, and contracts with the same name are
+    /// prefixed with the directories of their sources.
+    ///
     /// # Example
     ///
     /// ```text
@@ -95,13 +102,35 @@ impl MultiAbigen {
     /// ```
     pub fn from_json_files(root: impl AsRef<Path>) -> Result<Self> {
         let root = root.as_ref();
-        let files = util::json_files(root);
+        let files: Vec<_> = util::json_files(root)
+            .into_iter()
+            .filter(|path| artifacts::is_artifact_file(path))
+            .collect();
         eyre::ensure!(
             !files.is_empty(),
             "No json files found in directory: {}",
             root.display()
         );
-        files.into_iter().map(Abigen::from_file).collect()
+        let artifacts = files
+            .into_iter()
+            .map(|path| {
+                let mut artifact = Artifact::read(path)?;
+                artifact.name = artifacts::file_stem(&artifact.path).to_string();
+                Ok(artifact)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let names = artifacts::unique_names(&artifacts);
+        artifacts
+            .into_iter()
+            .zip(names)
+            .map(|(artifact, name)| {
+                let abigen = Abigen::from_file(&artifact.path)?;
+                Ok(Abigen {
+                    contract_name: syn::parse_str(&name)?,
+                    ..abigen
+                })
+            })
+            .collect()
     }
 
     /// See `apply_filter`
@@ -1563,4 +1592,50 @@ mod tests {
                 .expect("Inconsistent bindings");
         });
     }
+
+    #[test]
+    fn reads_hardhat_and_truffle_artifacts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let write = |path: &str, json: &str| {
+            let path = tmp.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, json).unwrap();
+        };
+        let hardhat = |source: &str| {
+            format!(
+                r#"{{"_format": "hh-sol-artifact-1", "contractName": "Token", "sourceName": "{source}", "abi": [], "bytecode": "0x6000", "deployedBytecode": "0x00", "linkReferences": {{}}, "deployedLinkReferences": {{}}}}"#
+            )
+        };
+        write(
+            "artifacts/contracts/a/Token.sol/Token.json",
+            &hardhat("contracts/a/Token.sol"),
+        );
+        write(
+            "artifacts/contracts/b/Token.sol/Token.json",
+            &hardhat("contracts/b/Token.sol"),
+        );
+        write(
+            "artifacts/contracts/a/Token.sol/Token.dbg.json",
+            r#"{"_format": "hh-sol-dbg-1", "buildInfo": "../../../build-info/abc.json"}"#,
+        );
+        write(
+            "artifacts/build-info/abc.json",
+            r#"{"id": "abc", "input": {}, "output": {}}"#,
+        );
+        // a Truffle build file, linking to a library
+        let placeholder = format!("__MathLib{}", "_".repeat(31));
+        write(
+            "artifacts/Calc.json",
+            &format!(
+                r#"{{"contractName": "Calc", "abi": [], "bytecode": "0x73{placeholder}00", "deployedBytecode": "0x73{placeholder}00", "sourcePath": "/project/contracts/Calc.sol", "networks": {{}}}}"#
+            ),
+        );
+
+        let gen = MultiAbigen::from_json_files(tmp.path().join("artifacts")).unwrap();
+        let mut names: Vec<_> = gen.iter().map(|g| g.contract_name.to_string()).collect();
+        names.sort();
+        assert_eq!(names, ["AToken", "BToken", "Calc"].map(String::from));
+        let bindings = gen.build().unwrap();
+        assert_eq!(bindings.len(), 3);
+    }
 }
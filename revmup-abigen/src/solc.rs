@@ -0,0 +1,364 @@
+//! Loading contracts from the output of `solc --standard-json` and `solc --combined-json`
+
+use crate::{
+    source::artifacts::{unique_names, Artifact},
+    Abigen, MultiAbigen, Source,
+};
+use ethers_core::utils::keccak256;
+use eyre::Result;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A contract compiled by solc
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolcContract {
+    /// The source file the contract is defined in
+    pub source: String,
+    /// The contract's name
+    pub name: String,
+    /// The contract as an artifact abigen reads, with its ABI, bytecode, link
+    /// references and source maps
+    pub artifact: Value,
+}
+
+impl SolcContract {
+    /// The fully qualified name of the contract, `<source>:<name>`
+    pub fn qualified_name(&self) -> String {
+        format!("{}:{}", self.source, self.name)
+    }
+
+    /// The hex creation bytecode, without a `0x` prefix and with library placeholders
+    /// if it needs linking
+    pub fn bytecode(&self) -> Option<&str> {
+        self.code("bytecode")
+            .map(|code| code.trim_start_matches("0x"))
+    }
+
+    /// The hex runtime bytecode, without a `0x` prefix
+    pub fn deployed_bytecode(&self) -> Option<&str> {
+        self.code("deployedBytecode")
+            .map(|code| code.trim_start_matches("0x"))
+    }
+
+    /// The source map of the creation bytecode
+    pub fn source_map(&self) -> Option<&str> {
+        self.code("sourceMap")
+    }
+
+    /// The source map of the runtime bytecode
+    pub fn deployed_source_map(&self) -> Option<&str> {
+        self.code("deployedSourceMap")
+    }
+
+    fn code(&self, key: &str) -> Option<&str> {
+        let pointer = match key {
+            "bytecode" => "/evm/bytecode/object",
+            "deployedBytecode" => "/evm/deployedBytecode/object",
+            "sourceMap" => "/evm/bytecode/sourceMap",
+            _ => "/evm/deployedBytecode/sourceMap",
+        };
+        self.artifact
+            .get(key)
+            .or_else(|| self.artifact.pointer(pointer))
+            .and_then(Value::as_str)
+            .filter(|code| !code.trim_start_matches("0x").is_empty())
+    }
+}
+
+/// Read the contracts of a `solc --standard-json` output file:
+/// `{ "contracts": { "<source>": { "<name>": { "abi": .., "evm": { .. } } } } }`
+pub fn read_standard_json(path: impl AsRef<Path>) -> Result<Vec<SolcContract>> {
+    let output = read_json(path.as_ref())?;
+    let Some(sources) = output.get("contracts").and_then(Value::as_object) else {
+        let errors = output
+            .get("errors")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|error| error.get("formattedMessage").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n");
+        eyre::bail!("solc output has no contracts\n{errors}");
+    };
+
+    let mut contracts = Vec::new();
+    for (source, compiled) in sources {
+        for (name, contract) in compiled.as_object().into_iter().flatten() {
+            eyre::ensure!(
+                contract.get("abi").is_some(),
+                "{source}:{name} has no abi, add it to the outputSelection"
+            );
+            contracts.push(SolcContract {
+                source: source.clone(),
+                name: name.clone(),
+                artifact: contract.clone(),
+            });
+        }
+    }
+    Ok(contracts)
+}
+
+/// Read the contracts of a `solc --combined-json abi,bin,bin-runtime` output file:
+/// `{ "contracts": { "<source>:<name>": { "abi": .., "bin": .., "bin-runtime": .. } } }`.
+/// The `srcmap` and `srcmap-runtime` source maps are kept if present.
+///
+/// The output has no link references, so they are found from the library placeholders,
+/// which hash the qualified names of the libraries.
+pub fn read_combined_json(path: impl AsRef<Path>) -> Result<Vec<SolcContract>> {
+    let output = read_json(path.as_ref())?;
+    let Some(compiled) = output.get("contracts").and_then(Value::as_object) else {
+        eyre::bail!("solc output has no contracts");
+    };
+
+    let placeholders: BTreeMap<String, (&str, &str)> = compiled
+        .keys()
+        .filter_map(|qualified| {
+            let (source, name) = qualified.rsplit_once(':')?;
+            Some((placeholder(qualified), (source, name)))
+        })
+        .collect();
+
+    let mut contracts = Vec::new();
+    for (qualified, contract) in compiled {
+        let Some((source, name)) = qualified.rsplit_once(':') else {
+            eyre::bail!("contract {qualified} is not named `<source>:<name>`");
+        };
+        // older solc encodes the abi as a string
+        let abi = match contract.get("abi") {
+            Some(Value::String(abi)) => serde_json::from_str(abi)?,
+            Some(abi) => abi.clone(),
+            None => eyre::bail!("{qualified} has no abi, add it to --combined-json"),
+        };
+        let code = |key| {
+            let hex = contract
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            Value::String(format!("0x{}", hex.trim_start_matches("0x")))
+        };
+        let bytecode = code("bin");
+        let deployed_bytecode = code("bin-runtime");
+        let mut artifact = json!({
+            "contractName": name,
+            "sourceName": source,
+            "abi": abi,
+            "linkReferences": link_references(bytecode.as_str().unwrap_or_default(), &placeholders)?,
+            "deployedLinkReferences": link_references(deployed_bytecode.as_str().unwrap_or_default(), &placeholders)?,
+            "bytecode": bytecode,
+            "deployedBytecode": deployed_bytecode,
+        });
+        for (key, renamed) in [
+            ("srcmap", "sourceMap"),
+            ("srcmap-runtime", "deployedSourceMap"),
+        ] {
+            if let Some(map) = contract.get(key) {
+                artifact[renamed] = map.clone();
+            }
+        }
+        contracts.push(SolcContract {
+            source: source.to_string(),
+            name: name.to_string(),
+            artifact,
+        });
+    }
+    Ok(contracts)
+}
+
+fn read_json(path: &Path) -> Result<Value> {
+    serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| eyre::eyre!("failed to read solc output {}: {e}", path.display()))
+}
+
+/// The placeholder solc leaves for the library with the qualified name: the first 34
+/// hex characters of its hash
+fn placeholder(qualified: &str) -> String {
+    let hash = hex::encode(keccak256(qualified));
+    format!("__${}$__", &hash[..34])
+}
+
+/// The link references of `bytecode`, in the shape of solc's `linkReferences`
+fn link_references(bytecode: &str, placeholders: &BTreeMap<String, (&str, &str)>) -> Result<Value> {
+    let hex = bytecode.trim_start_matches("0x");
+    let mut references: Map<String, Value> = Map::new();
+    let mut index = 0;
+    while let Some(found) = hex[index..].find("__$") {
+        let start = index + found;
+        let Some(placeholder) = hex.get(start..start + 40) else {
+            eyre::bail!("bytecode ends in a library placeholder");
+        };
+        let Some((source, name)) = placeholders.get(placeholder) else {
+            eyre::bail!("no library in the output matches the placeholder {placeholder}");
+        };
+        let libraries = references
+            .entry(source.to_string())
+            .or_insert_with(|| json!({}));
+        let offsets = libraries
+            .as_object_mut()
+            .expect("libraries by name")
+            .entry(name.to_string())
+            .or_insert_with(|| json!([]));
+        offsets
+            .as_array_mut()
+            .expect("placeholder offsets")
+            .push(json!({ "start": start / 2, "length": 20 }));
+        index = start + 40;
+    }
+    Ok(Value::Object(references))
+}
+
+/// An `Abigen` per contract, named after the contract, or prefixed with the directories
+/// of its source for contracts with the same name
+fn abigens(path: &Path, contracts: Vec<SolcContract>) -> Result<MultiAbigen> {
+    eyre::ensure!(
+        !contracts.is_empty(),
+        "No contracts found in solc output: {}",
+        path.display()
+    );
+    let artifacts: Vec<_> = contracts
+        .iter()
+        .map(|contract| Artifact {
+            path: path.to_path_buf(),
+            name: contract.name.clone(),
+            source: Some(PathBuf::from(&contract.source)),
+            has_abi: true,
+        })
+        .collect();
+    let names = unique_names(&artifacts);
+    contracts
+        .into_iter()
+        .zip(names)
+        .map(|(contract, name)| {
+            Ok(Abigen::new_raw(
+                syn::parse_str(&name)?,
+                Source::String(contract.artifact.to_string()),
+            ))
+        })
+        .collect()
+}
+
+impl MultiAbigen {
+    /// Reads every contract of a `solc --standard-json` output file. See
+    /// [`read_standard_json`].
+    ///
+    /// Each binding is named after the bare contract name. Only contracts sharing a name
+    /// are prefixed with the directories of their sources, e.g. `V1Token` and `V2Token`
+    /// for `src/v1/Token.sol:Token` and `src/v2/Token.sol:Token`. The `<source>:<name>`
+    /// name solc uses is [`SolcContract::qualified_name`].
+    pub fn from_solc_standard_json(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        abigens(path, read_standard_json(path)?)
+    }
+
+    /// Reads every contract of a `solc --combined-json` output file. See
+    /// [`read_combined_json`].
+    ///
+    /// Bindings are named like [`MultiAbigen::from_solc_standard_json`] names them:
+    /// after the bare contract name, prefixed with source directories only on a clash.
+    pub fn from_solc_combined_json(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        abigens(path, read_combined_json(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::Context;
+
+    fn names(gen: &MultiAbigen) -> Vec<String> {
+        let mut names: Vec<_> = gen.iter().map(|g| g.contract_name.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    fn write(json: &Value) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), json.to_string()).unwrap();
+        file
+    }
+
+    #[test]
+    fn reads_standard_json() {
+        let contract = |object: &str| {
+            json!({
+                "abi": [],
+                "metadata": "{}",
+                "evm": {
+                    "bytecode": { "object": object, "sourceMap": "1:2:0:-:0", "linkReferences": {} },
+                    "deployedBytecode": { "object": "00", "sourceMap": "3:4:0:-:0", "linkReferences": {} }
+                }
+            })
+        };
+        let output = write(&json!({
+            "contracts": {
+                "src/v1/Token.sol": { "Token": contract("6000"), "Math": contract("6001") },
+                "src/v2/Token.sol": { "Token": contract("6002") }
+            },
+            "sources": {}
+        }));
+
+        let contracts = read_standard_json(output.path()).unwrap();
+        assert_eq!(contracts.len(), 3);
+        assert_eq!(contracts[0].qualified_name(), "src/v1/Token.sol:Math");
+        assert_eq!(contracts[0].bytecode(), Some("6001"));
+        assert_eq!(contracts[0].deployed_bytecode(), Some("00"));
+        assert_eq!(contracts[0].source_map(), Some("1:2:0:-:0"));
+        assert_eq!(contracts[0].deployed_source_map(), Some("3:4:0:-:0"));
+
+        let gen = MultiAbigen::from_solc_standard_json(output.path()).unwrap();
+        assert_eq!(names(&gen), ["Math", "V1Token", "V2Token"]);
+        gen.build().unwrap();
+    }
+
+    #[test]
+    fn reads_combined_json_with_libraries() {
+        let library = placeholder("src/Math.sol:Math");
+        let output = write(&json!({
+            "contracts": {
+                "src/Math.sol:Math": {
+                    "abi": "[]",
+                    "bin": "6000",
+                    "bin-runtime": "00",
+                    "srcmap": "1:2:0:-:0"
+                },
+                "src/Calc.sol:Calc": {
+                    "abi": [{"type": "constructor", "inputs": [], "stateMutability": "nonpayable"}],
+                    "bin": format!("73{library}00"),
+                    "bin-runtime": format!("73{library}00")
+                }
+            },
+            "version": "0.8.21"
+        }));
+
+        let contracts = read_combined_json(output.path()).unwrap();
+        let calc = &contracts[0];
+        assert_eq!(calc.qualified_name(), "src/Calc.sol:Calc");
+        assert_eq!(
+            calc.artifact["linkReferences"],
+            json!({ "src/Math.sol": { "Math": [{ "start": 1, "length": 20 }] } })
+        );
+        assert_eq!(contracts[1].source_map(), Some("1:2:0:-:0"));
+        assert_eq!(contracts[1].bytecode(), Some("6000"));
+        assert_eq!(contracts[1].deployed_bytecode(), Some("00"));
+
+        let gen = MultiAbigen::from_solc_combined_json(output.path()).unwrap();
+        assert_eq!(names(&gen), ["Calc", "Math"]);
+        let calc = gen
+            .iter()
+            .find(|g| g.contract_name == "Calc")
+            .cloned()
+            .unwrap();
+        let deploy = Context::from_abigen(calc)
+            .unwrap()
+            .expand()
+            .unwrap()
+            .contract
+            .to_string();
+        assert!(deploy.contains("math : :: ethers_core :: types :: Address"));
+        gen.build().unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Locating contracts in the artifacts of Foundry, Hardhat and Truffle, and in plain
+//! ABI files
+
+use eyre::Result;
+use inflector::Inflector;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A contract artifact, and where its contract was compiled from
+#[derive(Debug)]
+pub(crate) struct Artifact {
+    /// The path of the artifact
+    pub(crate) path: PathBuf,
+    /// The contract's name, or the artifact's file name if unknown
+    pub(crate) name: String,
+    /// The contract's source file, if known
+    pub(crate) source: Option<PathBuf>,
+    /// Whether the artifact has an ABI, rather than being some other JSON output
+    pub(crate) has_abi: bool,
+}
+
+impl Artifact {
+    /// Read the artifact at `path`, which can be:
+    ///
+    /// - a Foundry artifact, with the source in `metadata.settings.compilationTarget`
+    /// - a Hardhat artifact, with `contractName` and `sourceName`
+    /// - a Truffle build file, with `contractName` and `sourcePath`
+    /// - an ABI, or an object with one
+    pub(crate) fn read(path: PathBuf) -> Result<Self> {
+        let json: Value = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| eyre::eyre!("failed to read artifact {}: {e}", path.display()))?;
+        let stem = file_stem(&path).to_string();
+        let Value::Object(artifact) = json else {
+            return Ok(Self {
+                path,
+                name: stem,
+                source: None,
+                has_abi: true,
+            });
+        };
+
+        let target = artifact
+            .get("metadata")
+            .and_then(|metadata| metadata.pointer("/settings/compilationTarget"))
+            .and_then(Value::as_object)
+            .and_then(|target| target.iter().next());
+        let (source, name) = match target {
+            Some((source, name)) => (Some(source.as_str()), name.as_str()),
+            None => {
+                let source = artifact
+                    .get("sourceName")
+                    .or_else(|| artifact.get("sourcePath"))
+                    .and_then(Value::as_str);
+                let name = artifact.get("contractName").and_then(Value::as_str);
+                (source, name)
+            }
+        };
+        Ok(Self {
+            name: name.map_or(stem, str::to_string),
+            source: source.map(PathBuf::from),
+            has_abi: artifact.contains_key("abi"),
+            path,
+        })
+    }
+}
+
+/// Returns whether `path` may be a contract artifact, rather than the debug files and
+/// build info that Hardhat and Foundry write next to them
+pub(crate) fn is_artifact_file(path: &Path) -> bool {
+    !path.to_string_lossy().ends_with(".dbg.json")
+        && !path.components().any(|c| c.as_os_str() == "build-info")
+}
+
+/// The name of a file without any of its extensions
+pub(crate) fn file_stem(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('.').next())
+        .unwrap_or_default()
+}
+
+/// Names for the contracts, prefixing those that share a name with the directories
+/// of their sources, from the closest one until the names are unique
+pub(crate) fn unique_names(artifacts: &[Artifact]) -> Vec<String> {
+    let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, artifact) in artifacts.iter().enumerate() {
+        by_name.entry(&artifact.name).or_default().push(i);
+    }
+
+    let mut names: Vec<String> = artifacts.iter().map(|a| a.name.clone()).collect();
+    for indices in by_name.values().filter(|indices| indices.len() > 1) {
+        let prefixes: Vec<Vec<String>> = indices
+            .iter()
+            .map(|&i| source_prefixes(&artifacts[i]))
+            .collect();
+        let longest = prefixes.iter().map(Vec::len).max().unwrap_or_default();
+        // the fewest directories that tell the sources apart, or all of them
+        let depth = (1..=longest)
+            .find(|&depth| {
+                let mut seen = HashSet::new();
+                prefixes
+                    .iter()
+                    .all(|prefix| seen.insert(prefix_at(prefix, depth)))
+            })
+            .unwrap_or(longest);
+        for (&i, prefix) in indices.iter().zip(&prefixes) {
+            names[i] = format!("{}{}", prefix_at(prefix, depth), artifacts[i].name);
+        }
+    }
+    names
+}
+
+/// The directories of the contract's source, closest first, in PascalCase. The
+/// source file comes first if it isn't named after the contract.
+fn source_prefixes(artifact: &Artifact) -> Vec<String> {
+    let Some(source) = &artifact.source else {
+        return Vec::new();
+    };
+    let stem = file_stem(source);
+    let parents = source
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter(|part| !part.is_empty() && *part != "/")
+        .rev();
+    (stem != artifact.name)
+        .then_some(stem)
+        .into_iter()
+        .chain(parents)
+        .map(|part| part.to_pascal_case())
+        .collect()
+}
+
+/// The `depth` closest prefixes, outermost first
+fn prefix_at(prefixes: &[String], depth: usize) -> String {
+    let depth = depth.min(prefixes.len());
+    prefixes[..depth].iter().rev().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(json: &str) -> Artifact {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Token.json");
+        fs::write(&path, json).unwrap();
+        Artifact::read(path).unwrap()
+    }
+
+    #[test]
+    fn reads_contract_sources() {
+        let foundry = read(
+            r#"{"abi": [], "metadata": {"settings": {"compilationTarget": {"src/v1/Token.sol": "Token"}}}}"#,
+        );
+        let hardhat = read(
+            r#"{"_format": "hh-sol-artifact-1", "contractName": "Token", "sourceName": "contracts/v2/Token.sol", "abi": []}"#,
+        );
+        let truffle = read(
+            r#"{"contractName": "Token", "abi": [], "metadata": "{}", "sourcePath": "/home/dev/project/contracts/Token.sol"}"#,
+        );
+        let abi = read("[]");
+        assert_eq!(foundry.source, Some("src/v1/Token.sol".into()));
+        assert_eq!(hardhat.source, Some("contracts/v2/Token.sol".into()));
+        assert_eq!(
+            truffle.source,
+            Some("/home/dev/project/contracts/Token.sol".into())
+        );
+        assert_eq!(abi.source, None);
+        assert!(!read(r#"{"_format": "hh-sol-dbg-1"}"#).has_abi);
+
+        assert_eq!(
+            unique_names(&[foundry, hardhat, truffle, abi]),
+            ["V1Token", "V2Token", "ContractsToken", "Token"]
+        );
+    }
+}
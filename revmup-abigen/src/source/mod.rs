@@ -2,6 +2,8 @@
 
 // TODO: Support `online` for WASM
 
+pub(crate) mod artifacts;
+
 #[cfg(all(feature = "online", not(target_arch = "wasm32")))]
 mod online;
 #[cfg(all(feature = "online", not(target_arch = "wasm32")))]
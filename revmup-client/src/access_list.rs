@@ -0,0 +1,280 @@
+//!
+//! EIP-2930 access lists generated from the addresses and slots a transaction touches
+//!
+use ethers_core::types::{
+    transaction::eip2930::{AccessList, AccessListItem, AccessListWithGasUsed},
+    H256, U256,
+};
+use revm::{
+    interpreter::{opcode, InstructionResult, Interpreter},
+    precompile::{Precompiles, SpecId as PrecompileSpecId},
+    primitives::{create_address, EVMError, TransactTo, TxEnv, B160, B256, U256 as RevmU256},
+    Database, EVMData, Inspector,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::BasicClient;
+
+/// An access list, and the gas a transaction uses with and without it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessListWithGas {
+    pub access_list: AccessList,
+    /// Gas used with the access list
+    pub gas_used: U256,
+    /// Gas used without an access list
+    pub gas_used_without_list: U256,
+}
+
+impl From<AccessListWithGas> for AccessListWithGasUsed {
+    fn from(list: AccessListWithGas) -> Self {
+        Self {
+            access_list: list.access_list,
+            gas_used: list.gas_used,
+        }
+    }
+}
+
+/// Records the addresses and storage slots an execution touches, like geth's
+/// access list tracer: the sender, the recipient and precompiles are only
+/// listed when their storage is used.
+#[derive(Debug, Clone, Default)]
+pub struct AccessListInspector {
+    excluded: HashSet<B160>,
+    touched: BTreeMap<B160, BTreeSet<B256>>,
+}
+
+impl AccessListInspector {
+    /// Start from the entries of `access_list`, never listing the `excluded`
+    /// addresses without storage
+    pub fn new(access_list: &AccessList, excluded: impl IntoIterator<Item = B160>) -> Self {
+        let mut touched: BTreeMap<B160, BTreeSet<B256>> = BTreeMap::new();
+        for item in &access_list.0 {
+            touched
+                .entry(item.address.into())
+                .or_default()
+                .extend(item.storage_keys.iter().map(|key| B256::from(*key)));
+        }
+        Self {
+            excluded: excluded.into_iter().collect(),
+            touched,
+        }
+    }
+
+    /// The addresses and storage slots touched so far
+    pub fn access_list(&self) -> AccessList {
+        let items = self
+            .touched
+            .iter()
+            .map(|(address, slots)| AccessListItem {
+                address: (*address).into(),
+                storage_keys: slots.iter().map(|slot| H256::from(*slot)).collect(),
+            })
+            .collect::<Vec<_>>();
+        items.into()
+    }
+
+    fn touch_address(&mut self, address: B160) {
+        if !self.excluded.contains(&address) {
+            self.touched.entry(address).or_default();
+        }
+    }
+
+    fn touch_slot(&mut self, address: B160, slot: RevmU256) {
+        self.touched
+            .entry(address)
+            .or_default()
+            .insert(B256::from(slot));
+    }
+}
+
+impl<DB: Database> Inspector<DB> for AccessListInspector {
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        _data: &mut EVMData<'_, DB>,
+        _is_static: bool,
+    ) -> InstructionResult {
+        let stack = interp.stack();
+        let word_address = |word: RevmU256| B160::from_slice(&B256::from(word)[12..]);
+        match interp.current_opcode() {
+            opcode::SLOAD | opcode::SSTORE => {
+                if let Ok(slot) = stack.peek(0) {
+                    self.touch_slot(interp.contract.address, slot);
+                }
+            }
+            opcode::BALANCE
+            | opcode::EXTCODESIZE
+            | opcode::EXTCODECOPY
+            | opcode::EXTCODEHASH
+            | opcode::SELFDESTRUCT => {
+                if let Ok(word) = stack.peek(0) {
+                    self.touch_address(word_address(word));
+                }
+            }
+            opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => {
+                if let Ok(word) = stack.peek(1) {
+                    self.touch_address(word_address(word));
+                }
+            }
+            _ => {}
+        }
+        InstructionResult::Continue
+    }
+}
+
+/// Convert an access list to the form of [`TxEnv::access_list`]
+pub fn to_tx_access_list(access_list: &AccessList) -> Vec<(B160, Vec<RevmU256>)> {
+    access_list
+        .0
+        .iter()
+        .map(|item| {
+            let keys = item
+                .storage_keys
+                .iter()
+                .map(|key| U256::from_big_endian(key.as_bytes()).into())
+                .collect();
+            (item.address.into(), keys)
+        })
+        .collect()
+}
+
+/// Convert the access list of a [`TxEnv`]
+pub fn from_tx_access_list(access_list: &[(B160, Vec<RevmU256>)]) -> AccessList {
+    let items = access_list
+        .iter()
+        .map(|(address, keys)| AccessListItem {
+            address: (*address).into(),
+            storage_keys: keys
+                .iter()
+                .map(|key| H256::from(B256::from(*key)))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    items.into()
+}
+
+impl BasicClient {
+    /// Generate the access list of `tx`, like `eth_createAccessList`. The transaction
+    /// is executed against the pending state without committing it, and the list
+    /// extends the one `tx` already has.
+    ///
+    /// As adding the list can change what the transaction touches, it is executed
+    /// again with the list until the list stops growing.
+    pub fn create_access_list(&self, tx: TxEnv) -> eyre::Result<AccessListWithGas> {
+        let without = TxEnv {
+            access_list: Vec::new(),
+            ..tx.clone()
+        };
+        let result = self.transact_ref_with(without, |evm| evm.transact_ref())?;
+        let gas_used_without_list = check_success(result)?;
+
+        let mut access_list = from_tx_access_list(&tx.access_list);
+        loop {
+            let with = TxEnv {
+                access_list: to_tx_access_list(&access_list),
+                ..tx.clone()
+            };
+            let mut inspector = None;
+            let result = self.transact_ref_with(with, |evm| {
+                let caller = evm.env.tx.caller;
+                let recipient = match evm.env.tx.transact_to {
+                    TransactTo::Call(to) => to,
+                    TransactTo::Create(_) => {
+                        let db = evm.db().expect("evm db");
+                        let nonce = db
+                            .basic(caller)
+                            .map_err(EVMError::Database)?
+                            .map_or(0, |info| info.nonce);
+                        create_address(caller, nonce)
+                    }
+                };
+                let precompiles =
+                    Precompiles::new(PrecompileSpecId::from_spec_id(evm.env.cfg.spec_id));
+                let excluded = [caller, recipient].into_iter().chain(
+                    precompiles
+                        .addresses()
+                        .into_iter()
+                        .map(|address| B160(*address)),
+                );
+                let tracer = inspector.insert(AccessListInspector::new(&access_list, excluded));
+                evm.inspect(tracer)
+            })?;
+            let gas_used = check_success(result)?;
+            let traced = inspector.expect("traced").access_list();
+            if traced == access_list {
+                return Ok(AccessListWithGas {
+                    access_list,
+                    gas_used,
+                    gas_used_without_list,
+                });
+            }
+            access_list = traced;
+        }
+    }
+}
+
+/// The gas used by a successful execution
+fn check_success(result: revm::primitives::ExecutionResult) -> eyre::Result<U256> {
+    let gas_used = result.gas_used();
+    crate::process_result_with_value(result)?;
+    Ok(gas_used.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RevmClient;
+    use ethers_core::types::{Address, Bytes};
+
+    // reads slot 1, then the balance of the address in its calldata
+    const READER: &str = "6008600c60003960086000f36001546000353150";
+
+    #[test]
+    fn lists_touched_addresses_and_slots() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let reader = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: READER.parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        let other: Address = crate::generate_random_account();
+
+        let mut data = [0u8; 32];
+        data[12..].copy_from_slice(other.as_bytes());
+        let tx = TxEnv {
+            caller: alice.into(),
+            transact_to: TransactTo::Call(reader.into()),
+            data: data.to_vec().into(),
+            ..Default::default()
+        };
+        let list = client.create_access_list(tx.clone()).unwrap();
+        // listed by address
+        let mut expected = vec![
+            AccessListItem {
+                address: reader,
+                storage_keys: vec![H256::from_low_u64_be(1)],
+            },
+            AccessListItem {
+                address: other,
+                storage_keys: vec![],
+            },
+        ];
+        expected.sort_by_key(|item| item.address);
+        assert_eq!(list.access_list, AccessList::from(expected));
+        // listing costs 2400 per address and 1900 per slot, and saves 2500 on the first
+        // access to `other` and 2000 on the slot. The recipient is warm anyway.
+        assert_eq!(list.gas_used, list.gas_used_without_list + 2200);
+
+        // sending it with the list uses the gas estimated
+        let with_list = TxEnv {
+            access_list: to_tx_access_list(&list.access_list),
+            ..tx
+        };
+        let (_, gas_used, _) = client.send_transaction(with_list).unwrap();
+        assert_eq!(U256::from(gas_used), list.gas_used);
+    }
+}
@@ -0,0 +1,482 @@
+//!
+//! State at past blocks, rebuilt from the values each block overwrote
+//!
+use ethers_core::types::{Address, Block, Bytes, H256, U256};
+use revm::{
+    db::{AccountState, DatabaseRef},
+    primitives::{
+        AccountInfo, BlockEnv, Bytecode, ExecutionResult, State, TxEnv, B160, B256,
+        U256 as RevmU256,
+    },
+    Database,
+};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, VecDeque},
+    io,
+};
+
+use crate::{BasicClient, ClientDB, History};
+
+/// The values a block overwrote, before it changed them
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BlockDiff {
+    accounts: HashMap<B160, Option<AccountInfo>>,
+    storage: HashMap<(B160, RevmU256), RevmU256>,
+}
+
+impl BlockDiff {
+    /// Record the values a transaction's `changes` overwrite in `db`, before they
+    /// are committed. Values already overwritten earlier in the block are kept.
+    pub(crate) fn record(&mut self, db: &mut ClientDB, changes: &State) {
+        for (address, account) in changes {
+            let previous = db.basic(*address).ok().flatten();
+            let changed = match &previous {
+                Some(info) => {
+                    account.is_destroyed
+                        || info.nonce != account.info.nonce
+                        || info.balance != account.info.balance
+                        || info.code_hash != account.info.code_hash
+                }
+                None => !account.is_destroyed,
+            };
+            if changed {
+                self.accounts
+                    .entry(*address)
+                    .or_insert_with(|| previous.map(|info| AccountInfo { code: None, ..info }));
+            }
+
+            // only the slots the client has seen are known to be cleared
+            if account.is_destroyed || account.storage_cleared {
+                if let Some(cached) = db.accounts.get(address) {
+                    if cached.account_state != AccountState::NotExisting {
+                        for (slot, value) in &cached.storage {
+                            self.storage.entry((*address, *slot)).or_insert(*value);
+                        }
+                    }
+                }
+            }
+            for (slot, value) in &account.storage {
+                if value.original_value != value.present_value {
+                    self.storage
+                        .entry((*address, *slot))
+                        .or_insert(value.original_value);
+                }
+            }
+        }
+    }
+
+    /// Record the values of `address` and its `slots` in `db`, before they are
+    /// written outside a transaction
+    pub(crate) fn record_write(
+        &mut self,
+        db: &ClientDB,
+        address: B160,
+        slots: impl IntoIterator<Item = RevmU256>,
+    ) -> io::Result<()> {
+        if let Entry::Vacant(entry) = self.accounts.entry(address) {
+            let previous = DatabaseRef::basic(db, address)?;
+            entry.insert(previous.map(|info| AccountInfo { code: None, ..info }));
+        }
+        for slot in slots {
+            if let Entry::Vacant(entry) = self.storage.entry((address, slot)) {
+                entry.insert(DatabaseRef::storage(db, address, slot)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the values `later` recorded, keeping those recorded here first
+    fn extend(&mut self, later: BlockDiff) {
+        for (address, info) in later.accounts {
+            self.accounts.entry(address).or_insert(info);
+        }
+        for (key, value) in later.storage {
+            self.storage.entry(key).or_insert(value);
+        }
+    }
+}
+
+/// The values overwritten by each retained block, indexed by what they're for
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StateDiffs {
+    /// The first block whose resulting state can be rebuilt
+    first: u64,
+    limit: Option<u64>,
+    accounts: HashMap<B160, BTreeMap<u64, Option<AccountInfo>>>,
+    storage: HashMap<(B160, RevmU256), BTreeMap<u64, RevmU256>>,
+    /// What each block changed, oldest first, to prune them
+    blocks: VecDeque<ChangedKeys>,
+    /// The values overwritten outside transactions since the latest block. They
+    /// belong to the next block
+    pending: BlockDiff,
+}
+
+/// The accounts and storage slots a block changed
+#[derive(Debug, Clone)]
+struct ChangedKeys {
+    number: u64,
+    accounts: Vec<B160>,
+    storage: Vec<(B160, RevmU256)>,
+}
+
+impl StateDiffs {
+    pub(crate) fn new(first: u64, limit: Option<u64>) -> Self {
+        Self {
+            first,
+            limit,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn push(&mut self, number: u64, block: BlockDiff) {
+        let mut diff = std::mem::take(&mut self.pending);
+        diff.extend(block);
+        let accounts = diff.accounts.keys().copied().collect();
+        let storage = diff.storage.keys().copied().collect();
+        for (address, info) in diff.accounts {
+            self.accounts
+                .entry(address)
+                .or_default()
+                .insert(number, info);
+        }
+        for (key, value) in diff.storage {
+            self.storage.entry(key).or_default().insert(number, value);
+        }
+        self.blocks.push_back(ChangedKeys {
+            number,
+            accounts,
+            storage,
+        });
+
+        let Some(limit) = self.limit else {
+            return;
+        };
+        while let Some(oldest) = self.blocks.front() {
+            if number - oldest.number < limit {
+                break;
+            }
+            let oldest = self.blocks.pop_front().expect("oldest block");
+            for address in oldest.accounts {
+                prune(&mut self.accounts, address, oldest.number);
+            }
+            for key in oldest.storage {
+                prune(&mut self.storage, key, oldest.number);
+            }
+            self.first = oldest.number;
+        }
+    }
+
+    /// The first block whose resulting state can still be rebuilt
+    pub(crate) fn first(&self) -> u64 {
+        self.first
+    }
+
+    /// The diff of the next block, for writes made outside transactions
+    pub(crate) fn pending_mut(&mut self) -> &mut BlockDiff {
+        &mut self.pending
+    }
+
+    /// The account as it was after block `number`, if it has changed since
+    fn account(&self, address: B160, number: u64) -> Option<Option<AccountInfo>> {
+        match self
+            .accounts
+            .get(&address)
+            .and_then(|infos| infos.range(number + 1..).next())
+        {
+            Some((_, info)) => Some(info.clone()),
+            None => self.pending.accounts.get(&address).cloned(),
+        }
+    }
+
+    /// The storage slot as it was after block `number`, if it has changed since
+    fn storage(&self, address: B160, slot: RevmU256, number: u64) -> Option<RevmU256> {
+        match self
+            .storage
+            .get(&(address, slot))
+            .and_then(|values| values.range(number + 1..).next())
+        {
+            Some((_, value)) => Some(*value),
+            None => self.pending.storage.get(&(address, slot)).copied(),
+        }
+    }
+}
+
+fn prune<K: std::hash::Hash + Eq, V>(
+    index: &mut HashMap<K, BTreeMap<u64, V>>,
+    key: K,
+    number: u64,
+) {
+    if let Some(values) = index.get_mut(&key) {
+        values.remove(&number);
+        if values.is_empty() {
+            index.remove(&key);
+        }
+    }
+}
+
+/// The client's state as it was after a past block
+#[derive(Clone, Copy)]
+pub(crate) struct HistoricalDB<'a> {
+    db: &'a ClientDB,
+    history: &'a History,
+    number: u64,
+}
+
+impl DatabaseRef for HistoricalDB<'_> {
+    type Error = io::Error;
+
+    fn basic(&self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        match self.history.state_diffs().account(address, self.number) {
+            Some(info) => Ok(info),
+            None => DatabaseRef::basic(self.db, address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        DatabaseRef::code_by_hash(self.db, code_hash)
+    }
+
+    fn storage(&self, address: B160, index: RevmU256) -> Result<RevmU256, Self::Error> {
+        match self
+            .history
+            .state_diffs()
+            .storage(address, index, self.number)
+        {
+            Some(value) => Ok(value),
+            None => DatabaseRef::storage(self.db, address, index),
+        }
+    }
+
+    fn block_hash(&self, number: RevmU256) -> Result<B256, Self::Error> {
+        let hash = self
+            .history
+            .block_hash(number.saturating_to())
+            .unwrap_or_default();
+        Ok(hash.into())
+    }
+}
+
+/// The environment a block was executed in
+pub(crate) fn block_env(block: &Block<H256>) -> BlockEnv {
+    BlockEnv {
+        number: RevmU256::from(block.number.unwrap_or_default().as_u64()),
+        coinbase: block.author.unwrap_or_default().into(),
+        timestamp: block.timestamp.into(),
+        difficulty: block.difficulty.into(),
+        prevrandao: block.mix_hash.map(Into::into),
+        basefee: block.base_fee_per_gas.unwrap_or_default().into(),
+        gas_limit: block.gas_limit.into(),
+    }
+}
+
+impl BasicClient {
+    /// Run `f` against the state as it was after block `number`
+    pub(crate) fn at_block<R>(
+        &self,
+        number: u64,
+        f: impl FnOnce(&HistoricalDB<'_>, &Block<H256>) -> eyre::Result<R>,
+    ) -> eyre::Result<R> {
+        let mut evm = self.evm();
+        let history = self.history();
+        let Some(block) = history.block(number) else {
+            eyre::bail!("block {number} has not been mined");
+        };
+        if number < history.state_diffs().first() {
+            eyre::bail!("the state at block {number} is no longer retained");
+        }
+        let db = HistoricalDB {
+            db: evm.db().expect("evm db"),
+            history: &history,
+            number,
+        };
+        f(&db, block)
+    }
+
+    /// Get the balance of `account` after block `number`
+    pub fn get_balance_at(&self, account: Address, number: u64) -> eyre::Result<U256> {
+        self.at_block(number, |db, _| {
+            let info = db.basic(account.into())?;
+            Ok(info.map(|info| info.balance.into()).unwrap_or_default())
+        })
+    }
+
+    /// Get the nonce of `account` after block `number`
+    pub fn get_nonce_at(&self, account: Address, number: u64) -> eyre::Result<u64> {
+        self.at_block(number, |db, _| {
+            let info = db.basic(account.into())?;
+            Ok(info.map(|info| info.nonce).unwrap_or_default())
+        })
+    }
+
+    /// Get the code deployed at `account` after block `number`
+    pub fn get_code_at(&self, account: Address, number: u64) -> eyre::Result<Bytes> {
+        self.at_block(number, |db, _| {
+            let code = match db.basic(account.into())? {
+                Some(AccountInfo {
+                    code: Some(code), ..
+                }) => code,
+                Some(info) => db.code_by_hash(info.code_hash)?,
+                None => return Ok(Bytes::default()),
+            };
+            Ok(code.original_bytes().into())
+        })
+    }
+
+    /// Get the value of the storage `slot` of `account` after block `number`
+    pub fn get_storage_at_block(
+        &self,
+        account: Address,
+        slot: U256,
+        number: u64,
+    ) -> eyre::Result<U256> {
+        self.at_block(number, |db, _| {
+            Ok(db.storage(account.into(), slot.into())?.into())
+        })
+    }
+
+    /// Make a read-only call against the state after block `number`. See
+    /// [`RevmClient::call_at`](crate::RevmClient::call_at)
+    pub fn call_at(&self, tx: TxEnv, number: u64) -> eyre::Result<revm::primitives::Bytes> {
+        let (output, _, _) = crate::process_result_with_value(self.transact_at(tx, number)?)?;
+        Ok(output)
+    }
+
+    /// Execute `tx` without committing it, against the state after block `number`
+    /// and in that block's environment. Registered inspectors don't run.
+    pub(crate) fn transact_at(&self, tx: TxEnv, number: u64) -> eyre::Result<ExecutionResult> {
+        let mut env = self.evm().env.clone();
+        self.at_block(number, |db, block| {
+            env.block = block_env(block);
+            self.transact_detached(env, *db, tx)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_random_account, ClientBuilder, RevmClient};
+    use ethers_core::types::Bytes;
+    use revm::primitives::TransactTo;
+
+    // increments slot 0 when called without calldata, returns it otherwise
+    const COUNTER: &str =
+        "601a600c600039601a6000f336600e57600054600101600055005b60005460005260206000f3";
+
+    fn deploy_counter(client: &BasicClient, caller: Address) -> Address {
+        client
+            .deploy(TxEnv {
+                caller: caller.into(),
+                transact_to: TransactTo::create(),
+                data: COUNTER.parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    fn call(caller: Address, counter: Address, data: &[u8]) -> TxEnv {
+        TxEnv {
+            caller: caller.into(),
+            transact_to: TransactTo::Call(counter.into()),
+            data: data.to_vec().into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_state_at_past_blocks() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(100)).unwrap();
+        let bob = generate_random_account();
+        let counter = deploy_counter(&client, alice);
+        for _ in 0..3 {
+            client.send_transaction(call(alice, counter, &[])).unwrap();
+            client.transfer(bob, alice, U256::from(10)).unwrap();
+        }
+        assert_eq!(client.block_number(), U256::from(7));
+
+        let count = |number| {
+            let output = client.call_at(call(alice, counter, &[1]), number).unwrap();
+            U256::from_big_endian(&output)
+        };
+        for (number, expected) in [(1, 0), (2, 1), (3, 1), (4, 2), (7, 3)] {
+            assert_eq!(count(number), U256::from(expected));
+            assert_eq!(
+                client
+                    .get_storage_at_block(counter, U256::zero(), number)
+                    .unwrap(),
+                U256::from(expected)
+            );
+        }
+        assert_eq!(client.get_balance_at(bob, 2).unwrap(), U256::zero());
+        assert_eq!(client.get_balance_at(bob, 3).unwrap(), U256::from(10));
+        // alice was funded after block 0
+        assert_eq!(client.get_balance_at(alice, 0).unwrap(), U256::zero());
+        assert_eq!(client.get_balance_at(alice, 1).unwrap(), U256::from(100));
+        assert_eq!(client.get_balance_at(alice, 7).unwrap(), U256::from(70));
+        // the counter didn't exist yet
+        assert!(client
+            .call_at(call(alice, counter, &[1]), 0)
+            .unwrap()
+            .is_empty());
+        assert!(client.get_balance_at(alice, 8).is_err());
+    }
+
+    #[test]
+    fn records_writes_outside_blocks() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(100)).unwrap();
+        let counter = deploy_counter(&client, alice);
+        client.send_transaction(call(alice, counter, &[])).unwrap();
+
+        // written after block 2, before any other block is mined
+        let bob = client.create_account_with_balance(U256::from(5)).unwrap();
+        let code = client.get_code(counter);
+        let storage = HashMap::from([(U256::zero(), U256::from(9))]);
+        client.etch(counter, code, storage).unwrap();
+        let mut state = client.dump_state().unwrap();
+        state.accounts.get_mut(&alice).unwrap().balance = U256::from(1);
+        client.load_state(&state).unwrap();
+
+        let at = |number| {
+            (
+                client.get_balance_at(alice, number).unwrap(),
+                client.get_balance_at(bob, number).unwrap(),
+                client
+                    .get_storage_at_block(counter, U256::zero(), number)
+                    .unwrap(),
+            )
+        };
+        let before = (U256::from(100), U256::zero(), U256::one());
+        let after = (U256::from(1), U256::from(5), U256::from(9));
+        assert_eq!(at(2), before);
+        let output = client.call_at(call(alice, counter, &[1]), 2).unwrap();
+        assert_eq!(U256::from_big_endian(&output), U256::one());
+
+        // the writes belong to the next block
+        client.transfer(bob, alice, U256::zero()).unwrap();
+        assert_eq!(at(2), before);
+        assert_eq!(at(3), after);
+    }
+
+    #[test]
+    fn prunes_old_state() {
+        let client = ClientBuilder::new().state_history(2).build();
+        let alice = client.create_account_with_balance(U256::from(100)).unwrap();
+        let bob = generate_random_account();
+        for _ in 0..4 {
+            client.transfer(bob, alice, U256::one()).unwrap();
+        }
+        assert!(client.get_balance_at(bob, 1).is_err());
+        assert_eq!(client.get_balance_at(bob, 2).unwrap(), U256::from(2));
+        assert_eq!(client.get_balance_at(bob, 3).unwrap(), U256::from(3));
+
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(200)).unwrap();
+        for _ in 0..=crate::builder::DEFAULT_STATE_HISTORY {
+            client.transfer(bob, alice, U256::one()).unwrap();
+        }
+        assert!(client.get_balance_at(alice, 0).is_err());
+        assert_eq!(client.get_balance_at(alice, 1).unwrap(), U256::from(199));
+    }
+}
@@ -0,0 +1,159 @@
+//!
+//! Blocks of several transactions
+//!
+use ethers_core::types::{TransactionReceipt, H256, U256};
+use revm::primitives::{ExecutionResult, TxEnv};
+
+use crate::BasicClient;
+
+/// The outcome of executing transactions for a block
+#[derive(Default)]
+pub(crate) struct Mined {
+    pub(crate) block: Option<H256>,
+    pub(crate) included: Vec<(H256, ExecutionResult)>,
+    pub(crate) deferred: Vec<TxEnv>,
+    pub(crate) rejected: Vec<(TxEnv, eyre::Report)>,
+}
+
+/// A mined block of transactions
+#[derive(Debug)]
+pub struct SealedBlock {
+    /// The block number
+    pub number: u64,
+    /// The block hash
+    pub hash: H256,
+    /// The gas used by all of the block's transactions
+    pub gas_used: U256,
+    /// Receipts of the included transactions, in execution order. Reverted
+    /// transactions are included with a failed status.
+    pub receipts: Vec<TransactionReceipt>,
+    /// Transactions left out because their gas limit exceeded the gas left in the
+    /// block, or because they set a nonce after a rejected transaction of their sender
+    pub deferred: Vec<TxEnv>,
+    /// Transactions that were invalid, e.g. a wrong nonce or too little balance
+    pub rejected: Vec<(TxEnv, eyre::Report)>,
+}
+
+/// Collects transactions to execute together in the next block.
+///
+/// ```
+/// # use revmup_client::BasicClient;
+/// # use revm::primitives::TxEnv;
+/// let client = BasicClient::new();
+/// let mut block = client.open_block();
+/// block.add(TxEnv::default()).add(TxEnv::default());
+/// let sealed = block.seal();
+/// assert_eq!(sealed.number, 1);
+/// ```
+#[derive(Debug)]
+#[must_use = "transactions are not executed until the block is sealed"]
+pub struct BlockBuilder<'a> {
+    client: &'a BasicClient,
+    transactions: Vec<TxEnv>,
+}
+
+impl<'a> BlockBuilder<'a> {
+    /// Queue a transaction. Transactions execute in the order they are added
+    pub fn add(&mut self, tx: TxEnv) -> &mut Self {
+        self.transactions.push(tx);
+        self
+    }
+
+    /// Execute the queued transactions and mine them into a block
+    pub fn seal(self) -> SealedBlock {
+        self.client.mine_block(self.transactions)
+    }
+}
+
+impl BasicClient {
+    /// Open a block to execute several transactions in
+    pub fn open_block(&self) -> BlockBuilder<'_> {
+        BlockBuilder {
+            client: self,
+            transactions: Vec::new(),
+        }
+    }
+
+    /// Execute `txs` in order and mine them into a single block, within the block
+    /// gas limit. A transaction that doesn't set a gas limit may use the gas left
+    /// in the block, up to [`DEFAULT_TX_GAS_LIMIT`](crate::builder::DEFAULT_TX_GAS_LIMIT).
+    pub fn mine_block(&self, txs: Vec<TxEnv>) -> SealedBlock {
+        let mined = self.mine_transactions(txs, true);
+        let hash = mined.block.expect("empty blocks are sealed");
+
+        let history = self.history();
+        let block = history
+            .block_by_hash(hash)
+            .expect("sealed block is recorded");
+        let receipts = mined
+            .included
+            .iter()
+            .filter_map(|(hash, _)| history.receipt(*hash).cloned())
+            .collect();
+
+        SealedBlock {
+            number: block.number.unwrap_or_default().as_u64(),
+            hash,
+            gas_used: block.gas_used,
+            receipts,
+            deferred: mined.deferred,
+            rejected: mined.rejected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generate_random_account, ClientBuilder};
+    use ethers_core::{types::Address, utils::parse_ether};
+    use revm::primitives::{TransactTo, TxEnv};
+
+    fn transfer(from: Address, to: Address, value: ethers_core::types::U256) -> TxEnv {
+        TxEnv {
+            caller: from.into(),
+            transact_to: TransactTo::Call(to.into()),
+            value: value.into(),
+            gas_limit: 21_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn mines_transactions_into_one_block() {
+        let client = ClientBuilder::new().block_gas_limit(50_000.into()).build();
+        let one = parse_ether(1u8).unwrap();
+        let alice = client.create_account_with_balance(one).unwrap();
+        let (bob, carol) = (generate_random_account(), generate_random_account());
+
+        let mut block = client.open_block();
+        // bob can only pay carol after alice's transfer in the same block
+        block
+            .add(transfer(alice, bob, one))
+            .add(transfer(bob, carol, one))
+            .add(TxEnv {
+                nonce: Some(9),
+                gas_limit: 5_000,
+                ..transfer(alice, bob, 0.into())
+            })
+            .add(transfer(alice, bob, 0.into()));
+        let sealed = block.seal();
+
+        assert_eq!(sealed.number, 1);
+        assert_eq!(sealed.gas_used, 42_000.into());
+        assert_eq!(sealed.receipts.len(), 2);
+        assert_eq!(sealed.receipts[1].cumulative_gas_used, 42_000.into());
+        assert_eq!(sealed.receipts[1].block_hash, Some(sealed.hash));
+        assert_eq!(sealed.rejected.len(), 1);
+        assert_eq!(sealed.deferred.len(), 1);
+        assert_eq!(client.get_balance(carol), one);
+
+        let block = client.get_block(1).unwrap();
+        assert_eq!(block.hash, Some(sealed.hash));
+        assert_eq!(block.transactions.len(), 2);
+
+        // deferred transactions can go in the next block
+        let sealed = client.mine_block(sealed.deferred);
+        assert_eq!((sealed.number, sealed.receipts.len()), (2, 1));
+        assert_eq!(client.open_block().seal().number, 3);
+    }
+}
@@ -0,0 +1,358 @@
+//!
+//! Configuration of a [`BasicClient`]
+//!
+use ethers_core::{
+    types::{Address, U256},
+    utils::{ChainConfig, Genesis, GenesisAccount},
+};
+use revm::primitives::{Env, SpecId};
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use crate::{genesis::genesis_spec_id, BasicClient, ClientBackend, DiskDB, TxOrdering};
+
+/// Builder for a [`BasicClient`].
+///
+/// The defaults match `BasicClient::new()`: the latest hardfork, chain id 1, an
+/// unlimited block gas limit, no base fee and all of revm's checks enabled.
+///
+/// ```
+/// use revm::primitives::SpecId;
+/// use revmup_client::ClientBuilder;
+///
+/// let client = ClientBuilder::new()
+///     .spec_id(SpecId::SHANGHAI)
+///     .chain_id(31337)
+///     .code_size_limit(false)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    env: Env,
+    settings: Settings,
+    alloc: HashMap<Address, GenesisAccount>,
+    backend: ClientBackend,
+    create2_deployer: bool,
+}
+
+/// Client behaviour that isn't part of the evm's environment
+#[derive(Debug, Clone)]
+pub(crate) struct Settings {
+    pub(crate) nonce_check: bool,
+    pub(crate) priority_fee: U256,
+    pub(crate) automine: bool,
+    pub(crate) ordering: TxOrdering,
+    pub(crate) checkpoint_interval: Option<u64>,
+    pub(crate) state_history: Option<u64>,
+}
+
+/// The gas limit of transactions that don't set one, when the block has more gas
+/// left. Paying for gas up to the block gas limit would be out of reach with a base
+/// fee and an unlimited block.
+pub const DEFAULT_TX_GAS_LIMIT: u64 = 30_000_000;
+
+/// The number of recent blocks whose state is kept by default, like geth keeps in
+/// memory
+pub const DEFAULT_STATE_HISTORY: u64 = 128;
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        let mut env = Env::default();
+        env.block.gas_limit = U256::max_value().into();
+        Self {
+            env,
+            settings: Settings {
+                nonce_check: true,
+                priority_fee: U256::zero(),
+                automine: true,
+                ordering: TxOrdering::Fifo,
+                checkpoint_interval: None,
+                state_history: Some(DEFAULT_STATE_HISTORY),
+            },
+            alloc: HashMap::new(),
+            backend: ClientBackend::default(),
+            create2_deployer: false,
+        }
+    }
+
+    /// Set the hardfork rules of the evm
+    pub fn spec_id(mut self, spec_id: SpecId) -> Self {
+        self.env.cfg.spec_id = spec_id;
+        self
+    }
+
+    /// Set the chain id
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.env.cfg.chain_id = revm::primitives::U256::from(chain_id);
+        self
+    }
+
+    /// Set the gas limit of each block. Transactions that don't set a gas limit
+    /// are given the gas left in the block, up to [`DEFAULT_TX_GAS_LIMIT`].
+    pub fn block_gas_limit(mut self, gas_limit: U256) -> Self {
+        self.env.block.gas_limit = gas_limit.into();
+        self
+    }
+
+    /// Set the base fee of the first block. The base fee of each following block
+    /// is adjusted by EIP-1559 from the gas used by its parent, and is burned.
+    pub fn base_fee(mut self, base_fee: U256) -> Self {
+        self.env.block.basefee = base_fee.into();
+        self
+    }
+
+    /// Set the tip transactions pay when they don't set a gas price. A transaction
+    /// without a gas price pays it on top of the base fee.
+    pub fn priority_fee(mut self, priority_fee: U256) -> Self {
+        self.settings.priority_fee = priority_fee;
+        self
+    }
+
+    /// Set the address that is paid the transaction tips
+    pub fn coinbase(mut self, coinbase: Address) -> Self {
+        self.env.block.coinbase = coinbase.into();
+        self
+    }
+
+    /// Mine each transaction sent through `RevmupTransport` as soon as it is sent.
+    /// When disabled, they wait in the mempool for `BasicClient::mine`. Enabled by default
+    pub fn automine(mut self, enabled: bool) -> Self {
+        self.settings.automine = enabled;
+        self
+    }
+
+    /// Set the order the mempool's transactions are mined in. Defaults to FIFO
+    pub fn ordering(mut self, ordering: TxOrdering) -> Self {
+        self.settings.ordering = ordering;
+        self
+    }
+
+    /// Keep what the last `blocks` blocks changed, to query the state at them with
+    /// `BasicClient::get_balance_at` and friends. Defaults to the last
+    /// [`DEFAULT_STATE_HISTORY`] blocks
+    pub fn state_history(mut self, blocks: u64) -> Self {
+        self.settings.state_history = Some(blocks);
+        self
+    }
+
+    /// Install the deterministic deployment proxy at
+    /// [`DETERMINISTIC_DEPLOYER`](crate::DETERMINISTIC_DEPLOYER), for CREATE2
+    /// deployments at the same addresses as on other chains. Disabled by default
+    pub fn create2_deployer(mut self, enabled: bool) -> Self {
+        self.create2_deployer = enabled;
+        self
+    }
+
+    /// Check the caller can pay for the gas and value of a transaction. Enabled by default
+    pub fn balance_check(mut self, enabled: bool) -> Self {
+        self.env.cfg.disable_balance_check = !enabled;
+        self
+    }
+
+    /// Check a transaction's nonce, when it sets one, matches the caller's.
+    /// Enabled by default
+    pub fn nonce_check(mut self, enabled: bool) -> Self {
+        self.settings.nonce_check = enabled;
+        self
+    }
+
+    /// Enforce the EIP-170 contract code size limit. Enabled by default
+    pub fn code_size_limit(mut self, enabled: bool) -> Self {
+        self.env.cfg.limit_contract_code_size = if enabled { None } else { Some(usize::MAX) };
+        self
+    }
+
+    /// Reject transactions from senders with deployed code (EIP-3607). Enabled by default
+    pub fn eip3607(mut self, enabled: bool) -> Self {
+        self.env.cfg.disable_eip3607 = !enabled;
+        self
+    }
+
+    /// Start from a geth-style genesis: fund and deploy the accounts of its `alloc`,
+    /// and take the genesis block's settings. The chain id and hardfork come from
+    /// its config, unless it has none. The hardfork is the latest one active at
+    /// genesis, later forks aren't scheduled.
+    pub fn genesis(mut self, genesis: Genesis) -> Self {
+        let block = &mut self.env.block;
+        block.number = revm::primitives::U256::from(genesis.number.unwrap_or_default().as_u64());
+        block.timestamp = revm::primitives::U256::from(genesis.timestamp.as_u64());
+        block.coinbase = genesis.coinbase.into();
+        block.difficulty = genesis.difficulty.into();
+        block.prevrandao = Some(genesis.mix_hash.into());
+        if !genesis.gas_limit.is_zero() {
+            block.gas_limit = revm::primitives::U256::from(genesis.gas_limit.as_u64());
+        }
+        if let Some(base_fee) = genesis.base_fee_per_gas {
+            block.basefee = base_fee.into();
+        }
+
+        if genesis.config != ChainConfig::default() {
+            self.env.cfg.chain_id = revm::primitives::U256::from(genesis.config.chain_id);
+            self.env.cfg.spec_id = genesis_spec_id(
+                &genesis.config,
+                genesis.timestamp.as_u64(),
+                genesis.difficulty,
+            );
+        }
+        self.alloc.extend(genesis.alloc);
+        self
+    }
+
+    /// Keep the client's state in a [`DiskDB`] at `path`, checkpointed after every
+    /// block unless `checkpoint_interval` says otherwise. If the store has been
+    /// checkpointed, the client resumes with its state, after its latest block.
+    ///
+    /// Only accounts are stored. The block history and mempool start empty.
+    pub fn persist(mut self, path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let store = DiskDB::open(path)?;
+        self.backend = ClientBackend::Disk(Arc::new(store));
+        self.settings.checkpoint_interval.get_or_insert(1);
+        Ok(self)
+    }
+
+    /// Checkpoint a persisted client's state every `blocks` blocks. Between
+    /// checkpoints, changed state is kept in memory and is lost if the process stops
+    pub fn checkpoint_interval(mut self, blocks: u64) -> Self {
+        self.settings.checkpoint_interval = Some(blocks.max(1));
+        self
+    }
+
+    /// Build the client
+    pub fn build(mut self) -> BasicClient {
+        let resumed = match &self.backend {
+            ClientBackend::Disk(store) => store.block(),
+            _ => None,
+        };
+        if let Some(block) = &resumed {
+            self.env.block = block.into();
+        }
+        let client = BasicClient::with_env(self.env, self.settings, self.backend);
+        // a resumed client's state already has its genesis, and may have changed it since
+        if resumed.is_none() {
+            if self.create2_deployer {
+                client.etch_deterministic_deployer();
+            }
+            client.insert_alloc(&self.alloc);
+            // they are the genesis block's state
+            client
+                .history
+                .write()
+                .expect("history lock poisoned")
+                .clear_pending_writes();
+        }
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RevmClient;
+    use ethers_core::{types::Bytes, utils::parse_ether};
+    use revm::primitives::{TransactTo, TxEnv};
+
+    fn create(caller: ethers_core::types::Address, code: &str) -> TxEnv {
+        TxEnv {
+            caller: caller.into(),
+            transact_to: TransactTo::create(),
+            data: code.parse::<Bytes>().unwrap().0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn configures_chain_and_hardfork() {
+        let client = ClientBuilder::new()
+            .chain_id(31337)
+            .base_fee(7.into())
+            .block_gas_limit(30_000_000.into())
+            .build();
+        assert_eq!(client.chain_id(), 31337.into());
+        assert_eq!(client.block_env().basefee, revm::primitives::U256::from(7));
+
+        // PUSH0 PUSH0 RETURN: PUSH0 was added in Shanghai
+        let push0 = "5f5ff3";
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let mut tx = create(alice, push0);
+        tx.gas_price = revm::primitives::U256::from(7);
+        assert!(client.deploy(tx).is_ok());
+
+        let merge = ClientBuilder::new().spec_id(SpecId::MERGE).build();
+        let alice = merge.create_account_with_balance(U256::zero()).unwrap();
+        assert!(merge.deploy(create(alice, push0)).is_err());
+    }
+
+    #[test]
+    fn sends_transactions_without_a_gas_limit_with_a_base_fee() {
+        // the block gas limit is unlimited
+        let client = ClientBuilder::new().base_fee(7.into()).build();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let bob = crate::generate_random_account();
+        client.transfer(bob, alice, 1.into()).unwrap();
+        assert_eq!(client.get_balance(bob), 1.into());
+
+        let receipt = client
+            .get_receipt(client.get_block(1).unwrap().transactions[0])
+            .unwrap();
+        assert_eq!(receipt.gas_used, Some(21_000.into()));
+        let paid = 21_000 * receipt.effective_gas_price.unwrap().as_u64();
+        assert_eq!(
+            client.get_balance(alice),
+            parse_ether(1u8).unwrap() - 1 - paid
+        );
+    }
+
+    #[test]
+    fn toggles_checks() {
+        // returns 0x6001 bytes of code, one more than EIP-170 allows
+        let oversized = "6160016000f3";
+        let one_byte = "60016000f3";
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::zero()).unwrap();
+        assert!(client.deploy(create(alice, oversized)).is_err());
+
+        let mut tx = create(alice, "00");
+        tx.gas_price = revm::primitives::U256::from(1);
+        assert!(client.deploy(tx.clone()).is_err());
+
+        tx.nonce = Some(5);
+        tx.gas_price = revm::primitives::U256::from(0);
+        assert!(client.deploy(tx.clone()).is_err());
+
+        let contract = client.deploy(create(alice, one_byte)).unwrap();
+        let from_contract = TxEnv {
+            caller: contract.into(),
+            transact_to: TransactTo::Call(alice.into()),
+            ..Default::default()
+        };
+        assert!(client.send_transaction(from_contract.clone()).is_err());
+
+        let client = ClientBuilder::new()
+            .code_size_limit(false)
+            .balance_check(false)
+            .nonce_check(false)
+            .eip3607(false)
+            .build();
+        let alice = client.create_account_with_balance(U256::zero()).unwrap();
+        assert!(client.deploy(create(alice, oversized)).is_ok());
+        tx.caller = alice.into();
+        tx.gas_price = revm::primitives::U256::from(1);
+        assert!(client.deploy(tx).is_ok());
+
+        let contract = client.deploy(create(alice, one_byte)).unwrap();
+        let from_contract = TxEnv {
+            caller: contract.into(),
+            ..from_contract
+        };
+        assert!(client.send_transaction(from_contract).is_ok());
+    }
+}
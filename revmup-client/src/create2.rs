@@ -0,0 +1,92 @@
+//!
+//! CREATE2 deployments and address prediction
+//!
+use ethers_core::{
+    types::{Address, H160, H256},
+    utils::get_create2_address,
+};
+use std::collections::HashMap;
+
+use crate::BasicClient;
+
+/// The address of the deterministic deployment proxy, at the same address on most
+/// chains. A transaction sending it a 32-byte salt followed by init code deploys
+/// the code with CREATE2, and returns the 20-byte address.
+pub const DETERMINISTIC_DEPLOYER: Address = H160([
+    0x4e, 0x59, 0xb4, 0x48, 0x47, 0xb3, 0x79, 0x57, 0x85, 0x88, 0x92, 0x0c, 0xa7, 0x8f, 0xbf, 0x26,
+    0xc0, 0xb4, 0x95, 0x6c,
+]);
+
+/// The runtime code of the deterministic deployment proxy
+pub const DETERMINISTIC_DEPLOYER_CODE: &str = "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe03601600081602082378035828234f58015156039578182fd5b8082525050506014600cf3";
+
+/// The address `init_code` is deployed at by CREATE2 from `deployer` with `salt`
+pub fn create2_address(deployer: Address, salt: H256, init_code: &[u8]) -> Address {
+    get_create2_address(deployer, salt, init_code)
+}
+
+impl BasicClient {
+    /// Install the deterministic deployment proxy at [`DETERMINISTIC_DEPLOYER`],
+    /// unless it's already there
+    pub(crate) fn etch_deterministic_deployer(&self) {
+        let existing = self.get_code(DETERMINISTIC_DEPLOYER);
+        if !existing.is_empty() {
+            return;
+        }
+        let code = DETERMINISTIC_DEPLOYER_CODE
+            .parse()
+            .expect("valid deployer code");
+        self.etch(DETERMINISTIC_DEPLOYER, code, HashMap::new())
+            .expect("etch the deterministic deployer");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ClientBuilder, RevmClient};
+    use ethers_core::types::{Bytes, U256};
+    use revm::primitives::{CreateScheme, TransactTo, TxEnv};
+
+    // returns runtime code `0x00`
+    const INIT_CODE: &str = "0x60016000f3";
+
+    #[test]
+    fn predicts_create2_addresses() {
+        let client = ClientBuilder::new().create2_deployer(true).build();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let init_code = INIT_CODE.parse::<Bytes>().unwrap();
+        let salt = H256::from_low_u64_be(7);
+
+        // through the deterministic deployment proxy
+        let mut data = salt.as_bytes().to_vec();
+        data.extend_from_slice(&init_code);
+        let output = client
+            .call(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::Call(DETERMINISTIC_DEPLOYER.into()),
+                data: data.into(),
+                ..Default::default()
+            })
+            .unwrap();
+        let predicted = create2_address(DETERMINISTIC_DEPLOYER, salt, &init_code);
+        assert_eq!(Address::from_slice(&output), predicted);
+
+        // natively, from the caller
+        let deployed = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::Create(CreateScheme::Create2 {
+                    salt: U256::from(7).into(),
+                }),
+                data: init_code.0.clone(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(deployed, create2_address(alice, salt, &init_code));
+        assert_eq!(client.get_code(deployed), "0x00".parse::<Bytes>().unwrap());
+
+        let client = BasicClient::new();
+        assert!(client.get_code(DETERMINISTIC_DEPLOYER).is_empty());
+    }
+}
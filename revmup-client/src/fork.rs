@@ -0,0 +1,348 @@
+//!
+//! Copy-on-write forks of a client
+//!
+use revm::{
+    db::{AccountState, CacheDB},
+    primitives::{B256, KECCAK_EMPTY},
+    EVM,
+};
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::{
+    inspector::Inspectors, subscription::Subscriptions, BasicClient, ClientBackend, ClientDB,
+};
+
+impl BasicClient {
+    /// Create an independent client starting from this client's state, history,
+    /// mempool and settings. Inspectors and log subscriptions aren't copied.
+    ///
+    /// The state and the mined blocks aren't copied: they are frozen and shared,
+    /// and each client keeps its own changes on top of them. Forking again without
+    /// changes in between shares the same frozen layer, so one client can be forked
+    /// many times cheaply. The state diffs kept for past blocks are shared until the
+    /// client or the fork next changes the state, which copies them.
+    ///
+    /// Forking after changes freezes them in a new layer that lookups go through,
+    /// unless no other fork shares the layer below, which then takes the changes.
+    /// A client that keeps forking while its forks are alive copies its layers into
+    /// one every 16 layers.
+    ///
+    /// A fork has no store. A client with a store can't checkpoint while its forks
+    /// are alive, as they read the store.
+    ///
+    /// ```
+    /// # use revmup_client::BasicClient;
+    /// # use ethers_core::types::U256;
+    /// let client = BasicClient::new();
+    /// let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+    /// let bob = revmup_client::generate_random_account();
+    ///
+    /// let fork = client.fork();
+    /// fork.transfer(bob, alice, U256::from(4)).unwrap();
+    /// assert_eq!(fork.get_balance(alice), U256::from(6));
+    /// assert_eq!(client.get_balance(alice), U256::from(10));
+    /// ```
+    pub fn fork(&self) -> BasicClient {
+        let mempool = self.mempool().clone();
+        let mut evm = self.evm();
+        let history = self.history.write().expect("history lock poisoned").fork();
+
+        let db = evm.db().expect("evm db");
+        let mut forked = CacheDB::new(ClientBackend::Fork(freeze(db)));
+        forked.block_hashes = db.block_hashes.clone();
+        let mut fork_evm = EVM::with_env(evm.env.clone());
+        fork_evm.database(forked);
+
+        let mut settings = self.settings.clone();
+        settings.checkpoint_interval = None;
+        BasicClient {
+            evm: Mutex::new(fork_evm),
+            history: RwLock::new(history),
+            subscriptions: Mutex::new(Subscriptions::default()),
+            inspectors: Mutex::new(Inspectors::default()),
+            mempool: Mutex::new(mempool),
+            checkpoint_error: Mutex::new(None),
+            settings,
+        }
+    }
+}
+
+/// The number of frozen layers a client's state or history can go through before
+/// they're copied into one
+pub(crate) const MAX_FROZEN_LAYERS: usize = 16;
+
+/// Freeze the state of `db`, which then keeps its changes on top of it
+fn freeze(db: &mut ClientDB) -> Arc<ClientDB> {
+    // nothing cached since the last freeze
+    if let ClientBackend::Fork(frozen) = &db.db {
+        let default_code = |hash: &B256| *hash == KECCAK_EMPTY || hash.is_zero();
+        if db.accounts.is_empty() && db.contracts.keys().all(default_code) {
+            return frozen.clone();
+        }
+    }
+    let block_hashes = db.block_hashes.clone();
+    let mut changes = std::mem::replace(db, CacheDB::new(ClientBackend::default()));
+    let frozen = match std::mem::take(&mut changes.db) {
+        // no other client shares the layer below, so it takes the changes
+        ClientBackend::Fork(below) => match Arc::try_unwrap(below) {
+            Ok(mut below) => {
+                merge(&mut below, changes);
+                below
+            }
+            Err(below) if layers(&below) >= MAX_FROZEN_LAYERS => {
+                let mut flat = flatten(&below);
+                merge(&mut flat, changes);
+                flat
+            }
+            Err(below) => CacheDB {
+                db: ClientBackend::Fork(below),
+                ..changes
+            },
+        },
+        backend => CacheDB {
+            db: backend,
+            ..changes
+        },
+    };
+    let frozen = Arc::new(frozen);
+    db.db = ClientBackend::Fork(frozen.clone());
+    db.block_hashes = block_hashes;
+    frozen
+}
+
+/// The number of frozen layers `db` is made of
+fn layers(db: &ClientDB) -> usize {
+    match &db.db {
+        ClientBackend::Fork(below) => 1 + layers(below),
+        _ => 1,
+    }
+}
+
+/// Copy the frozen layers of `db` into one
+fn flatten(db: &ClientDB) -> ClientDB {
+    let ClientBackend::Fork(below) = &db.db else {
+        return db.clone();
+    };
+    let mut flat = flatten(below);
+    merge(&mut flat, db.clone());
+    flat
+}
+
+/// Apply the changes cached in `top`, a layer on top of `base`, to `base`
+fn merge(base: &mut ClientDB, top: ClientDB) {
+    base.contracts.extend(top.contracts);
+    base.block_hashes = top.block_hashes;
+    for (address, account) in top.accounts {
+        let replaces = matches!(
+            account.account_state,
+            AccountState::NotExisting | AccountState::StorageCleared
+        );
+        match base.accounts.get_mut(&address) {
+            Some(below) if !replaces => {
+                below.info = account.info;
+                below.storage.extend(account.storage);
+                below.account_state = match below.account_state {
+                    // the layers further down are hidden either way
+                    AccountState::NotExisting | AccountState::StorageCleared => {
+                        AccountState::StorageCleared
+                    }
+                    AccountState::Touched => AccountState::Touched,
+                    AccountState::None => account.account_state,
+                };
+            }
+            _ => {
+                base.accounts.insert(address, account);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_random_account, RevmClient};
+    use ethers_core::types::{Bytes, U256};
+    use revm::primitives::{TransactTo, TxEnv};
+
+    // increments slot 0 each time it's called
+    const COUNTER: &str = "600a600c600039600a6000f360005460010160005500";
+
+    fn increment(
+        client: &BasicClient,
+        caller: ethers_core::types::Address,
+        counter: ethers_core::types::Address,
+    ) {
+        client
+            .send_transaction(TxEnv {
+                caller: caller.into(),
+                transact_to: TransactTo::Call(counter.into()),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn forks_evolve_independently() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let counter = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: COUNTER.parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        increment(&client, alice, counter);
+
+        let forks = (0..4).map(|_| client.fork()).collect::<Vec<_>>();
+        std::thread::scope(|scope| {
+            for (n, fork) in forks.iter().enumerate() {
+                scope.spawn(move || {
+                    for _ in 0..n {
+                        increment(fork, alice, counter);
+                    }
+                });
+            }
+        });
+        increment(&client, alice, counter);
+
+        for (n, fork) in forks.iter().enumerate() {
+            assert_eq!(
+                fork.get_storage_at(counter, U256::zero()),
+                U256::from(1 + n)
+            );
+            assert_eq!(fork.block_number(), U256::from(2 + n));
+        }
+        assert_eq!(client.get_storage_at(counter, U256::zero()), U256::from(2));
+        assert_eq!(client.get_nonce(alice), 3);
+
+        // a fork of a fork sees its parent's changes, not its siblings'
+        let nested = forks[3].fork();
+        increment(&nested, alice, counter);
+        assert_eq!(nested.get_storage_at(counter, U256::zero()), U256::from(5));
+        assert_eq!(
+            forks[3].get_storage_at(counter, U256::zero()),
+            U256::from(4)
+        );
+        assert_eq!(nested.get_balance(alice), U256::from(10));
+    }
+
+    #[test]
+    fn shares_unchanged_state() {
+        let client = BasicClient::new();
+        client.create_account_with_balance(U256::one()).unwrap();
+        let (a, b) = (client.fork(), client.fork());
+        let frozen = |client: &BasicClient| match &client.evm().db().unwrap().db {
+            ClientBackend::Fork(frozen) => Arc::as_ptr(frozen),
+            _ => unreachable!(),
+        };
+        assert_eq!(frozen(&a), frozen(&b));
+        assert_eq!(frozen(&client), frozen(&a));
+    }
+
+    #[test]
+    fn flattens_frozen_layers() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let counter = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: COUNTER.parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        let layers = |client: &BasicClient| layers(client.evm().db().unwrap());
+
+        let mut forks = Vec::new();
+        for _ in 0..2 * MAX_FROZEN_LAYERS {
+            increment(&client, alice, counter);
+            forks.push(client.fork());
+        }
+        assert!(layers(&client) <= MAX_FROZEN_LAYERS + 1);
+        for (n, fork) in forks.iter().enumerate() {
+            assert_eq!(
+                fork.get_storage_at(counter, U256::zero()),
+                U256::from(n + 1)
+            );
+            assert_eq!(fork.get_nonce(alice), n as u64 + 2);
+            assert_eq!(fork.get_code(counter), client.get_code(counter));
+        }
+        let count = 2 * MAX_FROZEN_LAYERS;
+        assert_eq!(
+            client.get_storage_at(counter, U256::zero()),
+            U256::from(count)
+        );
+        assert!(client.get_block(1).is_some());
+
+        // without other forks sharing it, the frozen layer takes the changes
+        drop(forks);
+        let before = layers(&client);
+        for _ in 0..4 {
+            increment(&client, alice, counter);
+            drop(client.fork());
+        }
+        assert_eq!(layers(&client), before);
+        assert_eq!(
+            client.get_storage_at(counter, U256::zero()),
+            U256::from(count + 4)
+        );
+    }
+
+    #[test]
+    fn dumps_state_shared_with_forks() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let counter = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: COUNTER.parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        increment(&client, alice, counter);
+        let before = client.dump_state().unwrap();
+
+        let fork = client.fork();
+        assert_eq!(client.dump_state().unwrap(), before);
+        assert_eq!(fork.dump_state().unwrap(), before);
+
+        increment(&fork, alice, counter);
+        let forked = fork.dump_state().unwrap();
+        assert_eq!(forked.accounts.len(), before.accounts.len());
+        assert_eq!(forked.accounts[&alice].nonce, 3);
+        assert_eq!(
+            forked.accounts[&counter].storage[&U256::zero()],
+            U256::from(2)
+        );
+        assert_eq!(
+            forked.accounts[&counter].code,
+            before.accounts[&counter].code
+        );
+        assert_eq!(client.dump_state().unwrap(), before);
+    }
+
+    #[test]
+    fn defers_checkpoints_while_forked() {
+        let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+        let client = BasicClient::builder().persist(&path).unwrap().build();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let bob = generate_random_account();
+
+        let fork = client.fork();
+        client.transfer(bob, alice, U256::one()).unwrap();
+        // the automatic checkpoint failed
+        assert!(client.take_checkpoint_error().is_some());
+        assert!(client.take_checkpoint_error().is_none());
+        assert!(client.checkpoint().is_err());
+        fork.transfer(bob, alice, U256::from(2)).unwrap();
+        drop(fork);
+
+        client.checkpoint().unwrap();
+        let resumed = BasicClient::builder().persist(&path).unwrap().build();
+        assert_eq!(resumed.get_balance(bob), U256::one());
+        assert_eq!(resumed.get_balance(alice), U256::from(9));
+    }
+}
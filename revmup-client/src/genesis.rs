@@ -0,0 +1,201 @@
+//!
+//! Clients initialised from a geth `genesis.json`
+//!
+use ethers_core::{
+    types::{Address, U256},
+    utils::{ChainConfig, Genesis, GenesisAccount},
+};
+use revm::primitives::{AccountInfo, Bytecode, SpecId};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{BasicClient, ClientBuilder};
+
+impl BasicClient {
+    /// Create a client from a geth-style `genesis.json`, with the accounts in its
+    /// `alloc` and the chain id, hardfork and block settings of its config. See
+    /// [`ClientBuilder::genesis`].
+    pub fn from_genesis(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let genesis: Genesis = serde_json::from_slice(&fs::read(path)?)?;
+        Ok(ClientBuilder::new().genesis(genesis).build())
+    }
+
+    /// Fund accounts and deploy code before any block is mined
+    pub(crate) fn insert_alloc(&self, alloc: &HashMap<Address, GenesisAccount>) {
+        let mut evm = self.evm();
+        let db = evm.db().expect("evm db");
+        for (address, account) in alloc {
+            let info = AccountInfo {
+                balance: account.balance.into(),
+                nonce: account.nonce.unwrap_or_default(),
+                code: account
+                    .code
+                    .as_ref()
+                    .map(|code| Bytecode::new_raw(code.0.clone())),
+                ..Default::default()
+            };
+            db.insert_account_info((*address).into(), info);
+            for (slot, value) in account.storage.iter().flatten() {
+                let slot = U256::from_big_endian(slot.as_bytes());
+                let value = U256::from_big_endian(value.as_bytes());
+                db.insert_account_storage((*address).into(), slot.into(), value.into())
+                    .expect("account was just inserted");
+            }
+        }
+    }
+}
+
+/// The latest hardfork active at the genesis block. A client runs a single
+/// hardfork, so forks scheduled after genesis don't apply.
+pub(crate) fn genesis_spec_id(config: &ChainConfig, timestamp: u64, difficulty: U256) -> SpecId {
+    let at_genesis = |block: Option<u64>| block == Some(0);
+    let by_time = |time: Option<u64>| time.is_some_and(|time| time <= timestamp);
+    let merged = config.terminal_total_difficulty_passed
+        || config
+            .terminal_total_difficulty
+            .is_some_and(|ttd| ttd <= difficulty);
+
+    if by_time(config.cancun_time) {
+        SpecId::CANCUN
+    } else if by_time(config.shanghai_time) {
+        SpecId::SHANGHAI
+    } else if merged {
+        SpecId::MERGE
+    } else if at_genesis(config.gray_glacier_block) {
+        SpecId::GRAY_GLACIER
+    } else if at_genesis(config.arrow_glacier_block) {
+        SpecId::ARROW_GLACIER
+    } else if at_genesis(config.london_block) {
+        SpecId::LONDON
+    } else if at_genesis(config.berlin_block) {
+        SpecId::BERLIN
+    } else if at_genesis(config.muir_glacier_block) {
+        SpecId::MUIR_GLACIER
+    } else if at_genesis(config.istanbul_block) {
+        SpecId::ISTANBUL
+    } else if at_genesis(config.petersburg_block) {
+        SpecId::PETERSBURG
+    } else if at_genesis(config.constantinople_block) {
+        SpecId::CONSTANTINOPLE
+    } else if at_genesis(config.byzantium_block) {
+        SpecId::BYZANTIUM
+    } else if at_genesis(config.eip158_block) {
+        SpecId::SPURIOUS_DRAGON
+    } else if at_genesis(config.eip150_block) {
+        SpecId::TANGERINE
+    } else if at_genesis(config.dao_fork_block) {
+        SpecId::DAO_FORK
+    } else if at_genesis(config.homestead_block) {
+        SpecId::HOMESTEAD
+    } else {
+        SpecId::FRONTIER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RevmClient;
+    use ethers_core::types::Bytes;
+    use revm::primitives::{TransactTo, TxEnv};
+
+    const GENESIS: &str = r#"{
+        "config": {
+            "chainId": 1337,
+            "homesteadBlock": 0,
+            "eip150Block": 0,
+            "eip155Block": 0,
+            "eip158Block": 0,
+            "byzantiumBlock": 0,
+            "constantinopleBlock": 0,
+            "petersburgBlock": 0,
+            "istanbulBlock": 0,
+            "berlinBlock": 0,
+            "londonBlock": 0,
+            "terminalTotalDifficulty": 0,
+            "terminalTotalDifficultyPassed": true,
+            "shanghaiTime": 0
+        },
+        "nonce": "0x0",
+        "timestamp": "0x6490fdd2",
+        "gasLimit": "0x1c9c380",
+        "difficulty": "0x0",
+        "baseFeePerGas": "0x3b9aca00",
+        "alloc": {
+            "0x00000000000000000000000000000000000000aa": {
+                "balance": "0xde0b6b3a7640000",
+                "nonce": "0x2"
+            },
+            "0x4200000000000000000000000000000000000016": {
+                "balance": "0x0",
+                "code": "0x60005460005260206000f3",
+                "storage": {
+                    "0x00": "0x2a"
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn builds_client_from_genesis() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), GENESIS).unwrap();
+        let client = BasicClient::from_genesis(file.path()).unwrap();
+
+        assert_eq!(client.chain_id(), 1337.into());
+        assert_eq!(
+            client.block_env().gas_limit,
+            revm::primitives::U256::from(30_000_000)
+        );
+        assert_eq!(client.get_block(0).unwrap().timestamp, 0x6490fdd2.into());
+
+        let funded: Address = "0x00000000000000000000000000000000000000aa"
+            .parse()
+            .unwrap();
+        assert_eq!(client.get_balance(funded), U256::exp10(18));
+        assert_eq!(client.get_nonce(funded), 2);
+
+        // the predeploy returns its slot 0
+        let predeploy: Address = "0x4200000000000000000000000000000000000016"
+            .parse()
+            .unwrap();
+        let output = client
+            .call(TxEnv {
+                caller: funded.into(),
+                transact_to: TransactTo::Call(predeploy.into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&output), 0x2a.into());
+        assert_eq!(
+            client.get_code(predeploy),
+            "0x60005460005260206000f3".parse::<Bytes>().unwrap()
+        );
+    }
+
+    #[test]
+    fn picks_hardfork_at_genesis() {
+        let genesis: Genesis = serde_json::from_str(GENESIS).unwrap();
+        let spec = |config: &ChainConfig| genesis_spec_id(config, 100, U256::zero());
+        assert_eq!(spec(&genesis.config), SpecId::SHANGHAI);
+
+        let config = ChainConfig {
+            shanghai_time: Some(101),
+            ..genesis.config.clone()
+        };
+        assert_eq!(spec(&config), SpecId::MERGE);
+
+        let config = ChainConfig {
+            terminal_total_difficulty: None,
+            terminal_total_difficulty_passed: false,
+            ..config
+        };
+        assert_eq!(spec(&config), SpecId::LONDON);
+        assert_eq!(
+            spec(&ChainConfig {
+                london_block: Some(10),
+                ..config
+            }),
+            SpecId::BERLIN
+        );
+    }
+}
@@ -0,0 +1,679 @@
+//!
+//! Record of the blocks, transactions and receipts mined by a client
+//!
+use ethers_core::{
+    types::{
+        Address, Block, BlockNumber, Bytes, Filter, FilterBlockOption, Log, Transaction,
+        TransactionReceipt, ValueOrArray, H256, U256, U64,
+    },
+    utils::keccak256,
+};
+use revm::primitives::{BlockEnv, Env, ExecutionResult, Output, TransactTo, TxEnv, B160};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    archive::{BlockDiff, StateDiffs},
+    fork::MAX_FROZEN_LAYERS,
+    ClientDB,
+};
+
+/// A transaction that was executed and committed in a block
+pub(crate) struct Executed {
+    pub(crate) hash: H256,
+    pub(crate) nonce: u64,
+    pub(crate) tx: TxEnv,
+    pub(crate) result: ExecutionResult,
+}
+
+/// Ordered history of the blocks mined by a client
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    records: Records,
+    block_hash_overrides: HashMap<u64, H256>,
+    /// Shared with forks until either side changes it
+    state_diffs: Arc<StateDiffs>,
+}
+
+/// The blocks, transactions and receipts mined by a client. Those mined before
+/// it was forked are frozen and shared with the fork.
+#[derive(Debug, Clone, Default)]
+struct Records {
+    frozen: Option<Arc<Records>>,
+    blocks: Vec<Block<H256>>,
+    block_numbers: HashMap<H256, u64>,
+    transactions: HashMap<H256, Transaction>,
+    receipts: HashMap<H256, TransactionReceipt>,
+}
+
+impl Records {
+    fn latest_block(&self) -> Option<&Block<H256>> {
+        match self.blocks.last() {
+            Some(block) => Some(block),
+            None => self.frozen.as_ref()?.latest_block(),
+        }
+    }
+
+    fn block(&self, number: u64) -> Option<&Block<H256>> {
+        // block numbers can skip ahead when a state is loaded
+        match self
+            .blocks
+            .binary_search_by_key(&Some(U64::from(number)), |block| block.number)
+        {
+            Ok(index) => self.blocks.get(index),
+            Err(_) => self.frozen.as_ref()?.block(number),
+        }
+    }
+
+    fn block_number(&self, hash: H256) -> Option<u64> {
+        match self.block_numbers.get(&hash) {
+            Some(number) => Some(*number),
+            None => self.frozen.as_ref()?.block_number(hash),
+        }
+    }
+
+    fn transaction(&self, hash: H256) -> Option<&Transaction> {
+        match self.transactions.get(&hash) {
+            Some(transaction) => Some(transaction),
+            None => self.frozen.as_ref()?.transaction(hash),
+        }
+    }
+
+    fn receipt(&self, hash: H256) -> Option<&TransactionReceipt> {
+        match self.receipts.get(&hash) {
+            Some(receipt) => Some(receipt),
+            None => self.frozen.as_ref()?.receipt(hash),
+        }
+    }
+
+    /// All the blocks, oldest first
+    fn blocks(&self) -> impl Iterator<Item = &Block<H256>> {
+        let mut layers = vec![self];
+        while let Some(frozen) = &layers[layers.len() - 1].frozen {
+            layers.push(frozen);
+        }
+        layers.into_iter().rev().flat_map(|layer| &layer.blocks)
+    }
+
+    fn layers(&self) -> usize {
+        1 + self.frozen.as_ref().map_or(0, |frozen| frozen.layers())
+    }
+
+    /// Add the records mined after these
+    fn append(&mut self, later: Records) {
+        self.blocks.extend(later.blocks);
+        self.block_numbers.extend(later.block_numbers);
+        self.transactions.extend(later.transactions);
+        self.receipts.extend(later.receipts);
+    }
+
+    /// Copy all the layers into one
+    fn flatten(&self) -> Records {
+        let Some(frozen) = &self.frozen else {
+            return self.clone();
+        };
+        let mut flat = frozen.flatten();
+        flat.append(Records {
+            frozen: None,
+            ..self.clone()
+        });
+        flat
+    }
+
+    /// Freeze the records mined so far, and return new records sharing them
+    fn freeze(&mut self) -> Records {
+        if !self.blocks.is_empty() {
+            let mut records = std::mem::take(self);
+            let frozen = match records.frozen.take() {
+                None => records,
+                // no other client shares the layer below, so it takes the new records
+                Some(below) => match Arc::try_unwrap(below) {
+                    Ok(mut below) => {
+                        below.append(records);
+                        below
+                    }
+                    Err(below) if below.layers() >= MAX_FROZEN_LAYERS => {
+                        let mut flat = below.flatten();
+                        flat.append(records);
+                        flat
+                    }
+                    Err(below) => Records {
+                        frozen: Some(below),
+                        ..records
+                    },
+                },
+            };
+            self.frozen = Some(Arc::new(frozen));
+        }
+        Records {
+            frozen: self.frozen.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The number of recent block hashes available to the `BLOCKHASH` opcode
+pub const BLOCK_HASH_HISTORY: u64 = 256;
+
+impl History {
+    /// Create a history starting with an empty genesis block built from `env`.
+    /// The state diffs of the last `state_limit` blocks are kept, or all of them.
+    pub(crate) fn new(env: &BlockEnv, state_limit: Option<u64>) -> Self {
+        let mut history = Self {
+            state_diffs: Arc::new(StateDiffs::new(env.number.saturating_to(), state_limit)),
+            ..Default::default()
+        };
+        history.push_block(env, Vec::new(), BlockDiff::default());
+        history
+    }
+
+    pub(crate) fn state_diffs(&self) -> &StateDiffs {
+        &self.state_diffs
+    }
+
+    /// Keep the values of `address` and its `slots` in `db` before they're written
+    /// outside a block, so the state at past blocks doesn't see the write
+    pub(crate) fn record_write(
+        &mut self,
+        db: &ClientDB,
+        address: B160,
+        slots: impl IntoIterator<Item = revm::primitives::U256>,
+    ) -> eyre::Result<()> {
+        Ok(Arc::make_mut(&mut self.state_diffs)
+            .pending_mut()
+            .record_write(db, address, slots)?)
+    }
+
+    /// Make the writes since the latest block part of its state, as if it made them
+    pub(crate) fn clear_pending_writes(&mut self) {
+        *Arc::make_mut(&mut self.state_diffs).pending_mut() = BlockDiff::default();
+    }
+
+    /// A history for a fork, sharing the blocks mined so far. The state diffs are
+    /// shared until one of them changes them.
+    pub(crate) fn fork(&mut self) -> History {
+        History {
+            records: self.records.freeze(),
+            block_hash_overrides: self.block_hash_overrides.clone(),
+            state_diffs: self.state_diffs.clone(),
+        }
+    }
+
+    /// The number of the most recently mined block
+    pub fn latest_block_number(&self) -> u64 {
+        self.latest_block().number.unwrap_or_default().as_u64()
+    }
+
+    /// The most recently mined block
+    pub fn latest_block(&self) -> &Block<H256> {
+        self.records.latest_block().expect("genesis block")
+    }
+
+    /// Get a mined block by number
+    pub fn block(&self, number: u64) -> Option<&Block<H256>> {
+        self.records.block(number)
+    }
+
+    /// Get a mined block by hash
+    pub fn block_by_hash(&self, hash: H256) -> Option<&Block<H256>> {
+        self.records
+            .block_number(hash)
+            .and_then(|number| self.block(number))
+    }
+
+    /// The hash `BLOCKHASH` returns for block `number`: its override if one was
+    /// set, else the hash of the mined block
+    pub fn block_hash(&self, number: u64) -> Option<H256> {
+        self.block_hash_overrides
+            .get(&number)
+            .copied()
+            .or_else(|| self.block(number).and_then(|block| block.hash))
+    }
+
+    /// The hashes `BLOCKHASH` can return while the block after the latest one is
+    /// being built: those of the last 256 blocks
+    pub(crate) fn recent_block_hashes(&self) -> impl Iterator<Item = (u64, H256)> + '_ {
+        let latest = self.latest_block_number();
+        let oldest = (latest + 1).saturating_sub(BLOCK_HASH_HISTORY);
+        (oldest..=latest).filter_map(|number| Some((number, self.block_hash(number)?)))
+    }
+
+    pub(crate) fn override_block_hash(&mut self, number: u64, hash: Option<H256>) {
+        match hash {
+            Some(hash) => self.block_hash_overrides.insert(number, hash),
+            None => self.block_hash_overrides.remove(&number),
+        };
+    }
+
+    /// Get a mined transaction by hash
+    pub fn transaction(&self, hash: H256) -> Option<&Transaction> {
+        self.records.transaction(hash)
+    }
+
+    /// Get the receipt of a mined transaction by hash
+    pub fn receipt(&self, hash: H256) -> Option<&TransactionReceipt> {
+        self.records.receipt(hash)
+    }
+
+    /// Resolve a block tag to a mined block number
+    pub fn resolve_block_number(&self, number: BlockNumber) -> u64 {
+        match number {
+            BlockNumber::Earliest => 0,
+            BlockNumber::Number(n) => n.as_u64(),
+            _ => self.latest_block_number(),
+        }
+    }
+
+    /// Return all logs matching `filter`, in the order they were emitted.
+    ///
+    /// This follows the semantics of `eth_getLogs`: an empty address or topic entry matches
+    /// anything and a list of values matches any one of them.
+    pub fn logs(&self, filter: &Filter) -> Vec<Log> {
+        let (from, to) = match filter.block_option {
+            FilterBlockOption::AtBlockHash(hash) => match self.records.block_number(hash) {
+                Some(number) => (number, number),
+                None => return Vec::new(),
+            },
+            FilterBlockOption::Range {
+                from_block,
+                to_block,
+            } => (
+                from_block.map_or(self.latest_block_number(), |n| self.resolve_block_number(n)),
+                to_block.map_or(self.latest_block_number(), |n| self.resolve_block_number(n)),
+            ),
+        };
+
+        self.records
+            .blocks()
+            .skip_while(|block| block.number.unwrap_or_default().as_u64() < from)
+            .take_while(|block| block.number.unwrap_or_default().as_u64() <= to)
+            .flat_map(|block| &block.transactions)
+            .filter_map(|hash| self.records.receipt(*hash))
+            .flat_map(|receipt| &receipt.logs)
+            .filter(|log| matches_log(filter, log))
+            .cloned()
+            .collect()
+    }
+
+    /// Seal the `executed` transactions into a new block built from `env`, numbered
+    /// after it. `diff` holds the state they overwrote.
+    ///
+    /// Returns the hash of the new block.
+    pub(crate) fn push_block(
+        &mut self,
+        env: &BlockEnv,
+        executed: Vec<Executed>,
+        diff: BlockDiff,
+    ) -> H256 {
+        let number = U64::from(env.number.saturating_to::<u64>());
+        Arc::make_mut(&mut self.state_diffs).push(number.as_u64(), diff);
+        let parent_hash = self
+            .records
+            .latest_block()
+            .and_then(|b| b.hash)
+            .unwrap_or_default();
+
+        let mut preimage = parent_hash.as_bytes().to_vec();
+        preimage.extend(H256::from_low_u64_be(number.as_u64()).as_bytes());
+        preimage.extend(H256::from(env.timestamp.to_be_bytes()).as_bytes());
+        for tx in &executed {
+            preimage.extend(tx.hash.as_bytes());
+        }
+        let block_hash = H256::from(keccak256(preimage));
+
+        let mut cumulative_gas_used = U256::zero();
+        let mut log_index = U256::zero();
+        let mut transactions = Vec::with_capacity(executed.len());
+        for (index, executed) in executed.into_iter().enumerate() {
+            let (transaction, mut receipt) = build_receipt(env, executed);
+            let index = U64::from(index);
+            cumulative_gas_used += receipt.gas_used.unwrap_or_default();
+
+            receipt.block_hash = Some(block_hash);
+            receipt.block_number = Some(number);
+            receipt.transaction_index = index;
+            receipt.cumulative_gas_used = cumulative_gas_used;
+            for log in receipt.logs.iter_mut() {
+                log.block_hash = Some(block_hash);
+                log.block_number = Some(number);
+                log.transaction_index = Some(index);
+                log.log_index = Some(log_index);
+                log_index += U256::one();
+            }
+
+            let transaction = Transaction {
+                block_hash: Some(block_hash),
+                block_number: Some(number),
+                transaction_index: Some(index),
+                ..transaction
+            };
+
+            transactions.push(transaction.hash);
+            let records = &mut self.records;
+            records.transactions.insert(transaction.hash, transaction);
+            records.receipts.insert(receipt.transaction_hash, receipt);
+        }
+
+        self.records.blocks.push(Block {
+            hash: Some(block_hash),
+            parent_hash,
+            author: Some(env.coinbase.into()),
+            number: Some(number),
+            gas_used: cumulative_gas_used,
+            gas_limit: env.gas_limit.into(),
+            timestamp: env.timestamp.into(),
+            difficulty: env.difficulty.into(),
+            mix_hash: env.prevrandao.map(Into::into),
+            base_fee_per_gas: Some(env.basefee.into()),
+            transactions,
+            ..Default::default()
+        });
+        self.records
+            .block_numbers
+            .insert(block_hash, number.as_u64());
+
+        block_hash
+    }
+}
+
+/// The base fee of the block after `block`, following EIP-1559
+pub(crate) fn next_base_fee(block: &Block<H256>) -> U256 {
+    const ELASTICITY_MULTIPLIER: u64 = 2;
+    const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+    let base_fee = block.base_fee_per_gas.unwrap_or_default();
+    let target = block.gas_limit / ELASTICITY_MULTIPLIER;
+    if base_fee.is_zero() || target.is_zero() || block.gas_used == target {
+        return base_fee;
+    }
+
+    // full width, the default block gas limit is `U256::MAX`
+    let delta = |gas_delta: U256| {
+        let delta = base_fee.full_mul(gas_delta) / target / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        U256::try_from(delta).expect("delta is less than the base fee")
+    };
+    if block.gas_used > target {
+        base_fee.saturating_add(delta(block.gas_used - target).max(U256::one()))
+    } else {
+        base_fee - delta(target - block.gas_used)
+    }
+}
+
+/// Deterministic hash identifying a transaction sent by `tx.caller` with `nonce`
+pub(crate) fn transaction_hash(tx: &TxEnv, nonce: u64) -> H256 {
+    let mut preimage = tx.caller.as_bytes().to_vec();
+    preimage.extend(nonce.to_be_bytes());
+    if let TransactTo::Call(to) = tx.transact_to {
+        preimage.extend(to.as_bytes());
+    }
+    preimage.extend(tx.value.to_be_bytes::<32>());
+    preimage.extend(tx.gas_limit.to_be_bytes());
+    preimage.extend(tx.gas_price.to_be_bytes::<32>());
+    if let Some(priority_fee) = tx.gas_priority_fee {
+        preimage.extend(priority_fee.to_be_bytes::<32>());
+    }
+    preimage.extend(tx.data.iter());
+    H256::from(keccak256(preimage))
+}
+
+/// Build the transaction and receipt for an executed transaction. Block related fields are
+/// filled in by the caller.
+fn build_receipt(env: &BlockEnv, executed: Executed) -> (Transaction, TransactionReceipt) {
+    let Executed {
+        hash,
+        nonce,
+        tx,
+        result,
+    } = executed;
+
+    let from: Address = tx.caller.into();
+    let to = match tx.transact_to {
+        TransactTo::Call(to) => Some(to.into()),
+        TransactTo::Create(_) => None,
+    };
+    let effective_gas_price: U256 = Env {
+        block: env.clone(),
+        tx: tx.clone(),
+        ..Default::default()
+    }
+    .effective_gas_price()
+    .into();
+    let transaction_type = U64::from(if tx.gas_priority_fee.is_some() { 2 } else { 0 });
+
+    let (status, gas_used, logs, contract_address) = match result {
+        ExecutionResult::Success {
+            gas_used,
+            logs,
+            output,
+            ..
+        } => {
+            let created = match output {
+                Output::Create(_, address) => address.map(Into::into),
+                Output::Call(_) => None,
+            };
+            (1u64, gas_used, logs, created)
+        }
+        ExecutionResult::Revert { gas_used, .. } => (0, gas_used, Vec::new(), None),
+        ExecutionResult::Halt { gas_used, .. } => (0, gas_used, Vec::new(), None),
+    };
+
+    let logs = logs
+        .into_iter()
+        .map(|log| Log {
+            address: log.address.into(),
+            topics: log.topics.into_iter().map(Into::into).collect(),
+            data: log.data.into(),
+            transaction_hash: Some(hash),
+            removed: Some(false),
+            ..Default::default()
+        })
+        .collect();
+
+    let transaction = Transaction {
+        hash,
+        nonce: nonce.into(),
+        from,
+        to,
+        value: tx.value.into(),
+        gas_price: Some(effective_gas_price),
+        gas: tx.gas_limit.into(),
+        input: Bytes::from(tx.data.clone()),
+        transaction_type: Some(transaction_type),
+        max_fee_per_gas: tx.gas_priority_fee.map(|_| tx.gas_price.into()),
+        max_priority_fee_per_gas: tx.gas_priority_fee.map(Into::into),
+        chain_id: tx.chain_id.map(Into::into),
+        ..Default::default()
+    };
+
+    let receipt = TransactionReceipt {
+        transaction_hash: hash,
+        from,
+        to,
+        gas_used: Some(gas_used.into()),
+        contract_address,
+        logs,
+        status: Some(status.into()),
+        transaction_type: Some(transaction_type),
+        effective_gas_price: Some(effective_gas_price),
+        ..Default::default()
+    };
+
+    (transaction, receipt)
+}
+
+/// Whether `log` matches the address and topics of `filter`, ignoring its block range
+pub(crate) fn matches_log(filter: &Filter, log: &Log) -> bool {
+    matches_address(filter, log) && matches_topics(filter, log)
+}
+
+fn matches_address(filter: &Filter, log: &Log) -> bool {
+    match &filter.address {
+        Some(ValueOrArray::Value(address)) => log.address == *address,
+        Some(ValueOrArray::Array(addresses)) => {
+            addresses.is_empty() || addresses.contains(&log.address)
+        }
+        None => true,
+    }
+}
+
+fn matches_topics(filter: &Filter, log: &Log) -> bool {
+    filter
+        .topics
+        .iter()
+        .enumerate()
+        .all(|(position, topic)| match topic {
+            None | Some(ValueOrArray::Value(None)) => true,
+            Some(ValueOrArray::Value(Some(expected))) => log.topics.get(position) == Some(expected),
+            Some(ValueOrArray::Array(expected)) => {
+                expected.is_empty()
+                    || expected.iter().any(Option::is_none)
+                    || log
+                        .topics
+                        .get(position)
+                        .is_some_and(|t| expected.contains(&Some(*t)))
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::primitives::{Log as RevmLog, B160, B256};
+
+    fn executed(nonce: u64, topics: Vec<B256>) -> Executed {
+        let tx = TxEnv {
+            caller: B160::from_low_u64_be(1),
+            transact_to: TransactTo::Call(B160::from_low_u64_be(2)),
+            ..Default::default()
+        };
+        Executed {
+            hash: transaction_hash(&tx, nonce),
+            nonce,
+            result: ExecutionResult::Success {
+                reason: revm::primitives::Eval::Stop,
+                gas_used: 21_000,
+                gas_refunded: 0,
+                logs: vec![RevmLog {
+                    address: B160::from_low_u64_be(2),
+                    topics,
+                    data: Default::default(),
+                }],
+                output: Output::Call(Default::default()),
+            },
+            tx,
+        }
+    }
+
+    #[test]
+    fn records_blocks_and_receipts() {
+        let mut env = BlockEnv::default();
+        let mut history = History::new(&env, None);
+        assert_eq!(history.latest_block_number(), 0);
+
+        env.number = revm::primitives::U256::from(1);
+        let first = executed(0, vec![B256::from_low_u64_be(7)]);
+        let hash = first.hash;
+        let block_hash =
+            history.push_block(&env, vec![first, executed(1, vec![])], BlockDiff::default());
+
+        assert_eq!(history.latest_block_number(), 1);
+        let block = history.block_by_hash(block_hash).unwrap();
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(block.gas_used, U256::from(42_000));
+        assert_eq!(block.parent_hash, history.block(0).unwrap().hash.unwrap());
+
+        let receipt = history.receipt(hash).unwrap();
+        assert_eq!(receipt.block_number, Some(1u64.into()));
+        assert_eq!(receipt.logs[0].log_index, Some(U256::zero()));
+        assert_eq!(
+            history.transaction(hash).unwrap().block_hash,
+            Some(block_hash)
+        );
+    }
+
+    #[test]
+    fn shares_blocks_with_forks() {
+        let mut env = BlockEnv::default();
+        let mut history = History::new(&env, None);
+        let mut forks = Vec::new();
+        for number in 1..=2 * MAX_FROZEN_LAYERS as u64 {
+            env.number = revm::primitives::U256::from(number);
+            let executed = executed(number, vec![B256::from_low_u64_be(number)]);
+            history.push_block(&env, vec![executed], BlockDiff::default());
+            forks.push(history.fork());
+        }
+        // forking without new blocks shares the same frozen layer
+        let shared = history.fork();
+        let frozen = |history: &History| Arc::as_ptr(history.records.frozen.as_ref().unwrap());
+        assert_eq!(frozen(&shared), frozen(&history));
+        assert!(shared.records.blocks.is_empty());
+        assert!(Arc::ptr_eq(&shared.state_diffs, &history.state_diffs));
+        drop(shared);
+
+        // the layers are copied into one before lookups go through too many
+        assert!(history.records.layers() <= MAX_FROZEN_LAYERS + 1);
+        let latest = 2 * MAX_FROZEN_LAYERS as u64;
+        assert_eq!(history.latest_block_number(), latest);
+        assert_eq!(
+            history.logs(&Filter::new().from_block(0)).len(),
+            latest as usize
+        );
+        for number in 0..=latest {
+            let block = history.block(number).unwrap();
+            assert_eq!(history.block_by_hash(block.hash.unwrap()), Some(block));
+        }
+        let first = transaction_hash(&executed(1, vec![]).tx, 1);
+        assert_eq!(
+            history.receipt(first).unwrap().block_number,
+            Some(1u64.into())
+        );
+
+        // a fork mines on top of the shared blocks without changing them
+        env.number = revm::primitives::U256::from(latest + 1);
+        let mut fork = forks.remove(0);
+        fork.push_block(&env, vec![], BlockDiff::default());
+        assert_eq!(fork.latest_block_number(), latest + 1);
+        assert_eq!(fork.block(latest), None);
+        assert_eq!(fork.block(1), history.block(1));
+        assert_eq!(history.latest_block_number(), latest);
+
+        // without other forks sharing them, new blocks join the frozen layer
+        drop((forks, fork));
+        let layers = history.records.layers();
+        env.number = revm::primitives::U256::from(latest + 1);
+        history.push_block(&env, vec![], BlockDiff::default());
+        drop(history.fork());
+        assert_eq!(history.records.layers(), layers);
+    }
+
+    #[test]
+    fn filters_logs() {
+        let mut env = BlockEnv::default();
+        let mut history = History::new(&env, None);
+        let topic = B256::from_low_u64_be(7);
+        env.number = revm::primitives::U256::from(1);
+        history.push_block(&env, vec![executed(0, vec![topic])], BlockDiff::default());
+        env.number = revm::primitives::U256::from(2);
+        history.push_block(
+            &env,
+            vec![executed(1, vec![B256::from_low_u64_be(8)])],
+            BlockDiff::default(),
+        );
+
+        let all = Filter::new().from_block(0);
+        assert_eq!(history.logs(&all).len(), 2);
+
+        let by_topic = all.clone().topic0(H256::from(topic));
+        assert_eq!(history.logs(&by_topic).len(), 1);
+
+        let by_range = all.clone().from_block(2).to_block(2);
+        let logs = history.logs(&by_range);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, Some(2u64.into()));
+
+        let by_address = all.address(Address::from_low_u64_be(3));
+        assert!(history.logs(&by_address).is_empty());
+
+        // without a range only the latest block is searched
+        assert_eq!(history.logs(&Filter::new()).len(), 1);
+    }
+}
@@ -0,0 +1,192 @@
+//!
+//! Custom `revm` inspectors run during client executions
+//!
+use revm::{
+    interpreter::{CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
+    primitives::{Bytes, B160, B256},
+    EVMData, Inspector,
+};
+use std::any::Any;
+
+use crate::ClientDB;
+
+/// An inspector that can be registered with a client.
+///
+/// Implemented for every `Send` [`Inspector`] of the client's database.
+pub trait ClientInspector: Inspector<ClientDB> + Any + Send {
+    /// Convert into `Any` so the concrete inspector can be recovered
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Inspector<ClientDB> + Any + Send> ClientInspector for T {
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Identifies an inspector registered with a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InspectorId(u64);
+
+/// The inspectors registered with a client
+#[derive(Default)]
+pub(crate) struct Inspectors {
+    next_id: u64,
+    entries: Vec<(InspectorId, Box<dyn ClientInspector>)>,
+}
+
+impl Inspectors {
+    pub(crate) fn insert(&mut self, inspector: Box<dyn ClientInspector>) -> InspectorId {
+        let id = InspectorId(self.next_id);
+        self.next_id += 1;
+        self.entries.push((id, inspector));
+        id
+    }
+
+    pub(crate) fn remove(&mut self, id: InspectorId) -> Option<Box<dyn ClientInspector>> {
+        let index = self.entries.iter().position(|(entry, _)| *entry == id)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Runs each registered inspector, in the order they were added.
+///
+/// Hooks that can interrupt execution stop at the first inspector that does not
+/// return `Continue`. The `*_end` hooks thread the outcome through every inspector.
+impl Inspector<ClientDB> for Inspectors {
+    fn initialize_interp(
+        &mut self,
+        interp: &mut Interpreter,
+        data: &mut EVMData<'_, ClientDB>,
+        is_static: bool,
+    ) -> InstructionResult {
+        for (_, inspector) in &mut self.entries {
+            let result = inspector.initialize_interp(interp, data, is_static);
+            if result != InstructionResult::Continue {
+                return result;
+            }
+        }
+        InstructionResult::Continue
+    }
+
+    fn step(
+        &mut self,
+        interp: &mut Interpreter,
+        data: &mut EVMData<'_, ClientDB>,
+        is_static: bool,
+    ) -> InstructionResult {
+        for (_, inspector) in &mut self.entries {
+            let result = inspector.step(interp, data, is_static);
+            if result != InstructionResult::Continue {
+                return result;
+            }
+        }
+        InstructionResult::Continue
+    }
+
+    fn log(
+        &mut self,
+        evm_data: &mut EVMData<'_, ClientDB>,
+        address: &B160,
+        topics: &[B256],
+        data: &Bytes,
+    ) {
+        for (_, inspector) in &mut self.entries {
+            inspector.log(evm_data, address, topics, data);
+        }
+    }
+
+    fn step_end(
+        &mut self,
+        interp: &mut Interpreter,
+        data: &mut EVMData<'_, ClientDB>,
+        is_static: bool,
+        eval: InstructionResult,
+    ) -> InstructionResult {
+        for (_, inspector) in &mut self.entries {
+            let result = inspector.step_end(interp, data, is_static, eval);
+            if result != InstructionResult::Continue {
+                return result;
+            }
+        }
+        InstructionResult::Continue
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, ClientDB>,
+        inputs: &mut CallInputs,
+        is_static: bool,
+    ) -> (InstructionResult, Gas, Bytes) {
+        for (_, inspector) in &mut self.entries {
+            let outcome = inspector.call(data, inputs, is_static);
+            if outcome.0 != InstructionResult::Continue {
+                return outcome;
+            }
+        }
+        (InstructionResult::Continue, Gas::new(0), Bytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        data: &mut EVMData<'_, ClientDB>,
+        inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+        is_static: bool,
+    ) -> (InstructionResult, Gas, Bytes) {
+        self.entries.iter_mut().fold(
+            (ret, remaining_gas, out),
+            |(ret, gas, out), (_, inspector)| {
+                inspector.call_end(data, inputs, gas, ret, out, is_static)
+            },
+        )
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, ClientDB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<B160>, Gas, Bytes) {
+        for (_, inspector) in &mut self.entries {
+            let outcome = inspector.create(data, inputs);
+            if outcome.0 != InstructionResult::Continue {
+                return outcome;
+            }
+        }
+        (
+            InstructionResult::Continue,
+            None,
+            Gas::new(0),
+            Bytes::default(),
+        )
+    }
+
+    fn create_end(
+        &mut self,
+        data: &mut EVMData<'_, ClientDB>,
+        inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<B160>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<B160>, Gas, Bytes) {
+        self.entries.iter_mut().fold(
+            (ret, address, remaining_gas, out),
+            |(ret, address, gas, out), (_, inspector)| {
+                inspector.create_end(data, inputs, ret, address, gas, out)
+            },
+        )
+    }
+
+    fn selfdestruct(&mut self, contract: B160, target: B160) {
+        for (_, inspector) in &mut self.entries {
+            inspector.selfdestruct(contract, target);
+        }
+    }
+}
@@ -3,29 +3,169 @@
 //!
 use ethers_core::{
     abi::{Address, RawLog},
-    types::{H256, U256},
+    types::{Block, Bytes, Filter, Log as EthersLog, Transaction, TransactionReceipt, H256, U256},
 };
 use rand::Rng;
 use revm::{
-    db::{CacheDB, EmptyDB},
-    primitives::{AccountInfo, ExecutionResult, Log, Output, ResultAndState, TransactTo, TxEnv},
-    Database, EVM,
+    db::{AccountState, CacheDB, DatabaseRef},
+    primitives::{
+        AccountInfo, BlockEnv, Bytecode, EVMResult, Env, ExecutionResult, Log, Output,
+        ResultAndState, TransactTo, TxEnv, B160,
+    },
+    Database, DatabaseCommit, EVM,
 };
-use std::cell::RefCell;
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{channel, Receiver},
+        Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard,
+    },
+};
+
+pub mod access_list;
+pub use access_list::{AccessListInspector, AccessListWithGas};
+
+pub mod archive;
+use archive::BlockDiff;
+
+pub mod block;
+use block::Mined;
+pub use block::{BlockBuilder, SealedBlock};
+
+pub mod builder;
+pub use builder::ClientBuilder;
+use builder::Settings;
+
+pub mod create2;
+pub use create2::{create2_address, DETERMINISTIC_DEPLOYER};
+
+pub mod fork;
+
+pub mod genesis;
+
+pub mod history;
+pub use history::History;
+
+pub mod link;
+pub use link::{LinkReferences, UnlinkedBytecode};
+
+pub mod mempool;
+use mempool::Mempool;
+pub use mempool::{PendingTransaction, TxComparator, TxOrdering};
+
+pub mod overrides;
+pub use overrides::{AccountOverride, BlockOverrides, StateOverride};
+
+pub mod state;
+pub use state::SerializableState;
+
+pub mod provider;
+pub use provider::RevmupTransport;
+
+pub mod inspector;
+use inspector::Inspectors;
+pub use inspector::{ClientInspector, InspectorId};
+
+pub mod store;
+pub use store::{ClientBackend, DiskDB};
+
+pub mod tracer;
+pub use tracer::{CallKind, CallTrace, CallTracer};
+
+pub mod subscription;
+use subscription::Subscriptions;
+pub use subscription::{LogCallback, SubscriptionId};
 
 /// Trait that defines the minimal methods required of a revmup client.  
+///
+/// Only `deploy`, `call` and `send_transaction` are required. The other methods
+/// return an error unless the client implements them.
 pub trait RevmClient {
     /// Deploy a contract
     fn deploy(&self, tx: TxEnv) -> eyre::Result<Address>;
 
+    /// Install runtime code and storage at an address, without running a constructor
+    fn etch(
+        &self,
+        _address: Address,
+        _code: Bytes,
+        _storage: HashMap<U256, U256>,
+    ) -> eyre::Result<()> {
+        unsupported("etch")
+    }
+
+    /// Deploy a contract, returning its address and the deployment's receipt
+    fn deploy_with_receipt(&self, _tx: TxEnv) -> eyre::Result<(Address, TransactionReceipt)> {
+        unsupported("deploy_with_receipt")
+    }
+
     /// Make a read-only call
     fn call(&self, tx: TxEnv) -> eyre::Result<revm::primitives::Bytes>;
 
+    /// Make a read-only call against the state as it was after block `number`, in
+    /// that block's environment
+    fn call_at(&self, _tx: TxEnv, _number: u64) -> eyre::Result<revm::primitives::Bytes> {
+        unsupported("call_at")
+    }
+
+    /// Make a read-only call that sees overridden accounts and block values
+    fn call_with_overrides(
+        &self,
+        _tx: TxEnv,
+        _state: &StateOverride,
+        _block: &BlockOverrides,
+    ) -> eyre::Result<revm::primitives::Bytes> {
+        unsupported("call_with_overrides")
+    }
+
+    /// Generate the access list of a transaction, and the gas it uses with and
+    /// without it
+    fn create_access_list(&self, _tx: TxEnv) -> eyre::Result<AccessListWithGas> {
+        unsupported("create_access_list")
+    }
+
     /// Send a transaction that commits to the db
     fn send_transaction(
         &self,
         tx: TxEnv,
     ) -> eyre::Result<(revm::primitives::Bytes, u64, Vec<RawLog>)>;
+
+    /// Send a transaction that commits to the db, returning its output and receipt
+    fn send_transaction_with_receipt(
+        &self,
+        _tx: TxEnv,
+    ) -> eyre::Result<(revm::primitives::Bytes, TransactionReceipt)> {
+        unsupported("send_transaction_with_receipt")
+    }
+
+    /// Add a transaction to the mempool, returning its hash
+    fn submit_transaction(&self, _tx: TxEnv) -> eyre::Result<H256> {
+        unsupported("submit_transaction")
+    }
+
+    /// Return the logs of mined transactions matching the filter
+    fn get_logs(&self, _filter: &Filter) -> eyre::Result<Vec<EthersLog>> {
+        unsupported("get_logs")
+    }
+
+    /// Call `callback` with each log matching `filter` as transactions are committed
+    fn subscribe_logs(
+        &self,
+        _filter: Filter,
+        _callback: LogCallback,
+    ) -> eyre::Result<SubscriptionId> {
+        unsupported("subscribe_logs")
+    }
+
+    /// Remove a log subscription
+    fn unsubscribe(&self, _id: SubscriptionId) -> eyre::Result<bool> {
+        unsupported("unsubscribe")
+    }
+}
+
+/// The error of a `RevmClient` method the client doesn't implement
+fn unsupported<T>(method: &str) -> eyre::Result<T> {
+    eyre::bail!("`{method}` is not supported by this client")
 }
 
 /// Utility to generate a random address
@@ -34,33 +174,106 @@ pub fn generate_random_account() -> Address {
     Address::from(random_bytes)
 }
 
+/// The database used by [`BasicClient`]
+pub type ClientDB = CacheDB<ClientBackend>;
+
 /// Basic implementation of a revmup client
 pub struct BasicClient {
-    evm: RefCell<EVM<CacheDB<EmptyDB>>>,
+    evm: Mutex<EVM<ClientDB>>,
+    history: RwLock<History>,
+    subscriptions: Mutex<Subscriptions>,
+    inspectors: Mutex<Inspectors>,
+    mempool: Mutex<Mempool>,
+    checkpoint_error: Mutex<Option<std::io::Error>>,
+    settings: Settings,
+}
+
+impl Default for BasicClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for BasicClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BasicClient").finish_non_exhaustive()
+    }
 }
 
 impl BasicClient {
     pub fn new() -> Self {
-        let mut evm = EVM::new();
-        let db = CacheDB::new(EmptyDB {});
-        evm.env.block.gas_limit = U256::max_value().into();
-        evm.database(db);
+        ClientBuilder::new().build()
+    }
+
+    /// Configure a new client
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    pub(crate) fn with_env(env: Env, settings: Settings, backend: ClientBackend) -> Self {
+        let mut evm = EVM::with_env(env);
+        evm.database(CacheDB::new(backend));
+
+        // the first block is the genesis, or the block a store was checkpointed at.
+        // Transactions are mined starting at the block after it
+        let history = History::new(&evm.env.block, settings.state_history);
+        advance_block(&mut evm.env.block);
+        serve_block_hashes(evm.db().expect("evm db"), &history);
+
         Self {
-            evm: RefCell::new(evm),
+            evm: Mutex::new(evm),
+            history: RwLock::new(history),
+            subscriptions: Mutex::new(Subscriptions::default()),
+            inspectors: Mutex::new(Inspectors::default()),
+            mempool: Mutex::new(Mempool::new(settings.ordering.clone())),
+            checkpoint_error: Mutex::new(None),
+            settings,
         }
     }
 
+    /// Lock the underlying evm
+    fn evm(&self) -> MutexGuard<'_, EVM<ClientDB>> {
+        self.evm.lock().expect("evm lock poisoned")
+    }
+
+    /// Lock the mempool. Taken before, never while holding, the evm lock
+    fn mempool(&self) -> MutexGuard<'_, Mempool> {
+        self.mempool.lock().expect("mempool lock poisoned")
+    }
+
+    /// Lock the error of the last automatic checkpoint
+    fn checkpoint_error(&self) -> MutexGuard<'_, Option<std::io::Error>> {
+        self.checkpoint_error
+            .lock()
+            .expect("checkpoint error lock poisoned")
+    }
+
+    /// Lock the registered inspectors. Taken while holding, never before, the evm lock
+    fn inspectors(&self) -> MutexGuard<'_, Inspectors> {
+        self.inspectors.lock().expect("inspectors lock poisoned")
+    }
+
+    /// Read access to the mined blocks, transactions and receipts
+    pub fn history(&self) -> RwLockReadGuard<'_, History> {
+        self.history.read().expect("history lock poisoned")
+    }
+
     /// Create and fund an account
     pub fn create_account_with_balance(&self, amount: U256) -> eyre::Result<Address> {
         let account = generate_random_account();
-        let mut info = AccountInfo::default();
-        info.balance = amount.into();
-        self.evm
-            .borrow_mut()
-            .db()
-            .and_then(|db| Some(db.insert_account_info(account.into(), info)));
+        let info = AccountInfo {
+            balance: amount.into(),
+            ..Default::default()
+        };
+        let mut evm = self.evm();
+        let db = evm.db().expect("evm db");
+        self.history
+            .write()
+            .expect("history lock poisoned")
+            .record_write(db, account.into(), [])?;
+        db.insert_account_info(account.into(), info);
 
-        Ok(account.into())
+        Ok(account)
     }
 
     /// Create and fund a number of accounts
@@ -70,53 +283,561 @@ impl BasicClient {
         amount: U256,
     ) -> eyre::Result<Vec<Address>> {
         let r = (0..num)
-            .into_iter()
             .flat_map(|_| self.create_account_with_balance(amount).ok())
             .collect();
         Ok(r)
     }
 
+    /// Install `code` at `address` without running a constructor, and set the
+    /// given `storage` slots. The account keeps its balance, nonce and other slots.
+    pub fn etch(
+        &self,
+        address: Address,
+        code: Bytes,
+        storage: HashMap<U256, U256>,
+    ) -> eyre::Result<()> {
+        let mut evm = self.evm();
+        let db = evm.db().expect("evm db");
+        let address: B160 = address.into();
+        self.history
+            .write()
+            .expect("history lock poisoned")
+            .record_write(db, address, storage.keys().map(|slot| (*slot).into()))?;
+        let existing = DatabaseRef::basic(&*db, address)?.unwrap_or_default();
+        let code = Bytecode::new_raw(code.0);
+        let info = AccountInfo {
+            code_hash: code.hash(),
+            code: Some(code),
+            ..existing
+        };
+        db.insert_account_info(address, info);
+        // an earlier lookup may have cached the account as missing
+        if let Some(account) = db.accounts.get_mut(&address) {
+            if matches!(account.account_state, AccountState::NotExisting) {
+                account.account_state = AccountState::Touched;
+            }
+        }
+        for (slot, value) in storage {
+            db.insert_account_storage(address, slot.into(), value.into())?;
+        }
+        Ok(())
+    }
+
     /// Get the account balance of the given account
     pub fn get_balance(&self, account: Address) -> U256 {
-        match self
-            .evm
-            .borrow_mut()
-            .db()
-            .expect("evm db")
-            .basic(account.into())
-        {
+        match self.evm().db().expect("evm db").basic(account.into()) {
             Ok(Some(account)) => account.balance.into(),
             _ => U256::zero(),
         }
     }
 
+    /// Get the nonce of the given account
+    pub fn get_nonce(&self, account: Address) -> u64 {
+        match self.evm().db().expect("evm db").basic(account.into()) {
+            Ok(Some(account)) => account.nonce,
+            _ => 0,
+        }
+    }
+
+    /// Get the deployed code of the given account
+    pub fn get_code(&self, account: Address) -> Bytes {
+        let mut evm = self.evm();
+        let db = evm.db().expect("evm db");
+        match db.basic(account.into()) {
+            Ok(Some(info)) => match info.code {
+                Some(code) => code.original_bytes().into(),
+                None => db
+                    .code_by_hash(info.code_hash)
+                    .map(|code| code.original_bytes().into())
+                    .unwrap_or_default(),
+            },
+            _ => Bytes::default(),
+        }
+    }
+
+    /// Get the value of the storage `slot` for the given account
+    pub fn get_storage_at(&self, account: Address, slot: U256) -> U256 {
+        self.evm()
+            .db()
+            .expect("evm db")
+            .storage(account.into(), slot.into())
+            .map(Into::into)
+            .unwrap_or_default()
+    }
+
+    /// The number of the most recently mined block
+    pub fn block_number(&self) -> U256 {
+        self.history().latest_block_number().into()
+    }
+
+    /// Get a mined transaction by hash
+    pub fn get_transaction(&self, hash: H256) -> Option<Transaction> {
+        self.history().transaction(hash).cloned()
+    }
+
+    /// Get the receipt of a mined transaction by hash
+    pub fn get_receipt(&self, hash: H256) -> Option<TransactionReceipt> {
+        self.history().receipt(hash).cloned()
+    }
+
+    /// Get a mined block by number
+    pub fn get_block(&self, number: u64) -> Option<Block<H256>> {
+        self.history().block(number).cloned()
+    }
+
+    /// Get the logs of all mined transactions matching `filter`. See `eth_getLogs`.
+    pub fn get_logs(&self, filter: &Filter) -> Vec<EthersLog> {
+        self.history().logs(filter)
+    }
+
+    /// The chain id used by the evm
+    pub fn chain_id(&self) -> U256 {
+        self.evm().env.cfg.chain_id.into()
+    }
+
+    /// The tip paid by transactions that don't set a gas price
+    pub fn priority_fee(&self) -> U256 {
+        self.settings.priority_fee
+    }
+
+    /// The block environment the next transaction executes in
+    pub fn block_env(&self) -> BlockEnv {
+        self.evm().env.block.clone()
+    }
+
     /// Transfer `amount` between accounts
     pub fn transfer(&self, to: Address, from: Address, amount: U256) -> eyre::Result<()> {
-        let mut tx = TxEnv::default();
-        tx.caller = from.into();
-        tx.transact_to = TransactTo::Call(to.into());
-        tx.value = amount.into();
-        self.evm.borrow_mut().env.tx = tx;
+        let tx = TxEnv {
+            caller: from.into(),
+            transact_to: TransactTo::Call(to.into()),
+            value: amount.into(),
+            ..Default::default()
+        };
         let (_, _, _) = self
-            .evm
-            .borrow_mut()
-            .transact_commit()
+            .mine_transaction(tx)
             .map_err(|e| eyre::eyre!("error on transact: {:?}", e))
-            .and_then(|r| process_execution_result(r))?;
+            .and_then(|(_, r)| process_execution_result(r))?;
 
         Ok(())
     }
+
+    /// Execute `tx`, commit the result to the db and record it in a new block.
+    ///
+    /// Returns the transaction hash with the result
+    pub(crate) fn mine_transaction(&self, tx: TxEnv) -> eyre::Result<(H256, ExecutionResult)> {
+        let mut mined = self.mine_transactions(vec![tx], false);
+        if let Some((_, error)) = mined.rejected.pop() {
+            return Err(error);
+        }
+        if !mined.deferred.is_empty() {
+            eyre::bail!("error on transact: gas limit exceeds the block gas limit");
+        }
+        Ok(mined.included.remove(0))
+    }
+
+    /// Execute `txs` in order, committing each to the db, and record the included
+    /// transactions in a new block. Transactions whose gas limit exceeds the gas
+    /// left in the block are deferred; invalid transactions are rejected. A rejected
+    /// sender's later transactions that set a nonce can't execute, and are deferred.
+    ///
+    /// A block without transactions is only sealed when `seal_empty` is set.
+    pub(crate) fn mine_transactions(&self, txs: Vec<TxEnv>, seal_empty: bool) -> Mined {
+        let mut evm = self.evm();
+        let mut inspectors = self.inspectors();
+        let block_gas_limit: u64 = evm.env.block.gas_limit.saturating_to();
+        let mut gas_used = 0u64;
+        let mut mined = Mined::default();
+        let mut executed = Vec::with_capacity(txs.len());
+        let mut diff = BlockDiff::default();
+
+        let mut deferred_senders = Vec::new();
+        let mut rejected_senders = Vec::new();
+        for original in txs {
+            let available = block_gas_limit - gas_used;
+            let tx = self.prepare_tx(original.clone(), available);
+            // a sender's later transactions wait for the deferred or rejected one
+            if tx.gas_limit > available
+                || deferred_senders.contains(&tx.caller)
+                || (tx.nonce.is_some() && rejected_senders.contains(&tx.caller))
+            {
+                deferred_senders.push(tx.caller);
+                mined.deferred.push(original);
+                continue;
+            }
+            let tx = self.fill_fees(&evm.env.block, tx);
+            let nonce = match tx.nonce {
+                Some(nonce) => nonce,
+                None => evm
+                    .db()
+                    .expect("evm db")
+                    .basic(tx.caller)
+                    .ok()
+                    .flatten()
+                    .map_or(0, |info| info.nonce),
+            };
+            // identify the transaction as it was sent, before the client filled it in
+            let hash = history::transaction_hash(&original, nonce);
+
+            evm.env.tx = tx.clone();
+            let result = if inspectors.is_empty() {
+                evm.transact()
+            } else {
+                evm.inspect(&mut *inspectors)
+            };
+            match result {
+                Ok(ResultAndState { result, state }) => {
+                    let db = evm.db().expect("evm db");
+                    diff.record(db, &state);
+                    db.commit(state);
+                    gas_used += result.gas_used();
+                    mined.included.push((hash, result.clone()));
+                    executed.push(history::Executed {
+                        hash,
+                        nonce,
+                        tx,
+                        result,
+                    });
+                }
+                Err(e) => {
+                    rejected_senders.push(tx.caller);
+                    mined
+                        .rejected
+                        .push((original, eyre::eyre!("error on transact: {:?}", e)))
+                }
+            }
+        }
+        drop(inspectors);
+
+        if executed.is_empty() && !seal_empty {
+            return mined;
+        }
+
+        let logs = {
+            let mut history = self.history.write().expect("history lock poisoned");
+            let block_hash = history.push_block(&evm.env.block, executed, diff);
+            evm.env.block.basefee = history::next_base_fee(history.latest_block()).into();
+            serve_block_hashes(evm.db().expect("evm db"), &history);
+            mined.block = Some(block_hash);
+            mined
+                .included
+                .iter()
+                .filter_map(|(hash, _)| history.receipt(*hash))
+                .flat_map(|receipt| receipt.logs.clone())
+                .collect::<Vec<_>>()
+        };
+        advance_block(&mut evm.env.block);
+        if let Some(interval) = self.settings.checkpoint_interval {
+            let latest = evm.env.block.number.saturating_to::<u64>() - 1;
+            if latest.is_multiple_of(interval) {
+                // a failed checkpoint keeps the state in memory, for the next one
+                *self.checkpoint_error() = checkpoint(&mut evm).err();
+            }
+        }
+        drop(evm);
+
+        self.notify_subscribers(&logs);
+        mined
+    }
+
+    /// Write the state changed since the last checkpoint to the client's store,
+    /// and drop it from memory. A client restarted from the store resumes at the
+    /// latest block. Does nothing if the client has no store.
+    ///
+    /// A client with a store checkpoints every `ClientBuilder::checkpoint_interval`
+    /// blocks on its own. It can't checkpoint while forks of it are alive, changes
+    /// stay in memory until they are dropped. If an automatic checkpoint fails, its
+    /// error is kept for [`BasicClient::take_checkpoint_error`].
+    pub fn checkpoint(&self) -> eyre::Result<()> {
+        let mut evm = self.evm();
+        let result = checkpoint(&mut evm);
+        if result.is_ok() {
+            *self.checkpoint_error() = None;
+        }
+        Ok(result?)
+    }
+
+    /// The error of the last automatic checkpoint, if it failed and no checkpoint
+    /// has succeeded since. Its changes are still in memory, and are written by the
+    /// next checkpoint that succeeds.
+    pub fn take_checkpoint_error(&self) -> Option<std::io::Error> {
+        self.checkpoint_error().take()
+    }
+
+    /// Make `BLOCKHASH` return `hash` for block `number`, mined or not. The
+    /// recorded block keeps its own hash.
+    pub fn set_block_hash(&self, number: u64, hash: H256) {
+        self.override_block_hash(number, Some(hash));
+    }
+
+    /// Remove the override of block `number`'s hash
+    pub fn clear_block_hash(&self, number: u64) {
+        self.override_block_hash(number, None);
+    }
+
+    fn override_block_hash(&self, number: u64, hash: Option<H256>) {
+        let mut evm = self.evm();
+        let mut history = self.history.write().expect("history lock poisoned");
+        history.override_block_hash(number, hash);
+        serve_block_hashes(evm.db().expect("evm db"), &history);
+    }
+
+    /// Whether transactions sent through `RevmupTransport` are mined immediately
+    pub fn automine(&self) -> bool {
+        self.settings.automine
+    }
+
+    /// The nonce of the sender's next transaction, after those in the mempool
+    pub fn get_pending_nonce(&self, account: Address) -> u64 {
+        let state_nonce = self.get_nonce(account);
+        self.mempool().next_nonce(account.into(), state_nonce)
+    }
+
+    /// Add `tx` to the mempool to be mined by [`BasicClient::mine`], returning its hash.
+    /// A transaction without a nonce is given the sender's pending nonce.
+    ///
+    /// A transaction with the same sender and nonce as a pending one replaces it if it
+    /// raises both its max fee and priority fee by at least 10%.
+    pub fn submit_transaction(&self, mut tx: TxEnv) -> eyre::Result<H256> {
+        let state_nonce = self.get_nonce(tx.caller.into());
+        let mut mempool = self.mempool();
+        let nonce = tx
+            .nonce
+            .unwrap_or_else(|| mempool.next_nonce(tx.caller, state_nonce));
+        if nonce < state_nonce {
+            eyre::bail!("nonce too low: {} < {}", nonce, state_nonce);
+        }
+        tx.nonce = Some(nonce);
+        let hash = history::transaction_hash(&tx, nonce);
+        mempool.insert(hash, tx)?;
+        Ok(hash)
+    }
+
+    /// The transactions waiting in the mempool, in submission order
+    pub fn pending_transactions(&self) -> Vec<PendingTransaction> {
+        let basefee = self.evm().env.block.basefee;
+        self.mempool()
+            .pending(|tx| self.effective_priority_fee(tx, basefee))
+    }
+
+    /// Change the order the mempool's transactions are mined in
+    pub fn set_ordering(&self, ordering: TxOrdering) {
+        self.mempool().ordering = ordering;
+    }
+
+    /// Mine the mempool's transactions into a block, by the ordering policy and within
+    /// the block gas limit. Each sender's transactions are mined in nonce order, from
+    /// its current nonce. Transactions that don't fit stay in the mempool.
+    pub fn mine(&self) -> SealedBlock {
+        let basefee = self.evm().env.block.basefee;
+        let pending = self.mempool().take_ordered(
+            |sender| self.get_nonce(sender.into()),
+            |tx| self.effective_priority_fee(tx, basefee),
+        );
+        let txs = pending.iter().map(|pending| pending.tx.clone()).collect();
+        let sealed = self.mine_block(txs);
+
+        let deferred = pending
+            .into_iter()
+            .filter(|pending| {
+                sealed
+                    .deferred
+                    .iter()
+                    .any(|tx| tx.caller == pending.tx.caller && tx.nonce == pending.tx.nonce)
+            })
+            .collect();
+        self.mempool().restore(deferred);
+        sealed
+    }
+
+    /// The tip per gas `tx` pays at `basefee`
+    fn effective_priority_fee(
+        &self,
+        tx: &TxEnv,
+        basefee: revm::primitives::U256,
+    ) -> revm::primitives::U256 {
+        if tx.gas_price.is_zero() && tx.gas_priority_fee.is_none() {
+            return self.settings.priority_fee.into();
+        }
+        tx.gas_priority_fee
+            .unwrap_or(tx.gas_price)
+            .min(tx.gas_price.saturating_sub(basefee))
+    }
+
+    /// Register an inspector that runs during every execution of the client:
+    /// `deploy`, `call`, `send_transaction` and `transfer`. Inspectors run in the
+    /// order they were added. Any `revm` inspector works, e.g.
+    /// `revm::inspectors::CustomPrintTracer`, as well as the built-in [`CallTracer`].
+    ///
+    /// Inspectors run while the client is locked, so they must not use the client.
+    pub fn add_inspector<I: ClientInspector>(&self, inspector: I) -> InspectorId {
+        self.inspectors().insert(Box::new(inspector))
+    }
+
+    /// Remove a registered inspector. Use `into_any` to recover its concrete type
+    pub fn remove_inspector(&self, id: InspectorId) -> Option<Box<dyn ClientInspector>> {
+        self.inspectors().remove(id)
+    }
+
+    /// Run `inspector` during the executions in `f`, then return it with the result of `f`.
+    ///
+    /// ```
+    /// # use revmup_client::BasicClient;
+    /// # use revm::inspectors::GasInspector;
+    /// let client = BasicClient::new();
+    /// let (_, gas) = client.inspect(GasInspector::default(), || {
+    ///     // calls and transactions...
+    /// });
+    /// ```
+    pub fn inspect<I, R>(&self, inspector: I, f: impl FnOnce() -> R) -> (R, I)
+    where
+        I: ClientInspector,
+    {
+        let id = self.add_inspector(inspector);
+        let result = f();
+        let inspector = self
+            .remove_inspector(id)
+            .and_then(|inspector| inspector.into_any().downcast::<I>().ok())
+            .expect("inspector removed during `inspect`");
+        (result, *inspector)
+    }
+
+    /// Call `callback` with each log matching `filter` as transactions are committed.
+    /// The block range of the filter is ignored.
+    ///
+    /// Callbacks run after the transaction is mined, so they may use the client.
+    pub fn subscribe_logs<F>(&self, filter: Filter, callback: F) -> SubscriptionId
+    where
+        F: Fn(&EthersLog) + Send + Sync + 'static,
+    {
+        self.subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .insert(filter, Box::new(callback))
+    }
+
+    /// Receive each log matching `filter` on a channel as transactions are committed
+    pub fn subscribe_logs_channel(&self, filter: Filter) -> (SubscriptionId, Receiver<EthersLog>) {
+        let (sender, receiver) = channel();
+        let id = self.subscribe_logs(filter, move |log| {
+            // the receiver may have been dropped
+            let _ = sender.send(log.clone());
+        });
+        (id, receiver)
+    }
+
+    /// Remove a log subscription. Returns false if it was not registered
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        self.subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .remove(id)
+    }
+
+    fn notify_subscribers(&self, logs: &[EthersLog]) {
+        if logs.is_empty() {
+            return;
+        }
+        let matching = self
+            .subscriptions
+            .lock()
+            .expect("subscriptions lock poisoned")
+            .matching(logs);
+        for (callback, log) in matching {
+            callback(&log);
+        }
+    }
+
+    /// Apply the client's configuration to `tx`
+    fn prepare_tx(&self, mut tx: TxEnv, available_gas: u64) -> TxEnv {
+        if !self.settings.nonce_check {
+            tx.nonce = None;
+        }
+        // the `TxEnv` default means no gas limit was set
+        if tx.gas_limit == u64::MAX {
+            tx.gas_limit = available_gas.min(builder::DEFAULT_TX_GAS_LIMIT);
+        }
+        tx
+    }
+
+    /// Give a transaction that doesn't set a gas price the base fee plus the client's tip,
+    /// allowing for the base fee to double
+    fn fill_fees(&self, block: &BlockEnv, mut tx: TxEnv) -> TxEnv {
+        let priority_fee = self.settings.priority_fee.into();
+        let unset = tx.gas_price.is_zero() && tx.gas_priority_fee.is_none();
+        if unset && !(block.basefee.is_zero() && self.settings.priority_fee.is_zero()) {
+            tx.gas_price = block
+                .basefee
+                .saturating_mul(revm::primitives::U256::from(2))
+                .saturating_add(priority_fee);
+            tx.gas_priority_fee = Some(priority_fee);
+        }
+        tx
+    }
+
+    /// Execute `tx` without committing the result
+    pub(crate) fn transact_ref(&self, tx: TxEnv) -> eyre::Result<ExecutionResult> {
+        self.transact_ref_with(tx, |evm| {
+            // locked after the evm, like when mining
+            let mut inspectors = self.inspectors();
+            if inspectors.is_empty() {
+                evm.transact_ref()
+            } else {
+                // `inspect` does not commit, and keeps inspectors on the client's database type
+                evm.inspect(&mut *inspectors)
+            }
+        })
+    }
+
+    /// Execute `tx` without committing the result, with `run` driving the client's EVM
+    pub(crate) fn transact_ref_with(
+        &self,
+        tx: TxEnv,
+        run: impl FnOnce(&mut EVM<ClientDB>) -> EVMResult<std::io::Error>,
+    ) -> eyre::Result<ExecutionResult> {
+        let mut evm = self.evm();
+        let block_gas_limit = evm.env.block.gas_limit.saturating_to();
+        evm.env.tx = self.prepare_tx(tx, block_gas_limit);
+        // like `eth_call`, the nonce is not checked, so calls can follow pending transactions
+        evm.env.tx.nonce = None;
+        // and a call without a gas price is not checked against the base fee
+        evm.env.cfg.disable_base_fee = evm.env.tx.gas_price.is_zero();
+        let result = run(&mut evm);
+        evm.env.cfg.disable_base_fee = false;
+        result
+            .map(|ResultAndState { result, .. }| result)
+            .map_err(|e| eyre::eyre!("error on transact: {:?}", e))
+    }
+
+    /// Execute `tx` like a call, against `db` and in `env` rather than the client's.
+    /// Registered inspectors don't run.
+    pub(crate) fn transact_detached<D>(
+        &self,
+        env: Env,
+        db: D,
+        tx: TxEnv,
+    ) -> eyre::Result<ExecutionResult>
+    where
+        D: DatabaseRef,
+        D::Error: std::fmt::Debug,
+    {
+        let mut evm = EVM::with_env(env);
+        let block_gas_limit = evm.env.block.gas_limit.saturating_to();
+        evm.env.tx = self.prepare_tx(tx, block_gas_limit);
+        evm.env.tx.nonce = None;
+        evm.env.cfg.disable_base_fee = evm.env.tx.gas_price.is_zero();
+        evm.database(db);
+        evm.transact_ref()
+            .map(|ResultAndState { result, .. }| result)
+            .map_err(|e| eyre::eyre!("error on transact: {:?}", e))
+    }
 }
 
 impl RevmClient for BasicClient {
     fn deploy(&self, tx: TxEnv) -> eyre::Result<Address> {
-        self.evm.borrow_mut().env.tx = tx;
         let (output, _, _) = self
-            .evm
-            .borrow_mut()
-            .transact_commit()
+            .mine_transaction(tx)
             .map_err(|e| eyre::eyre!("error on deploy: {:?}", e))
-            .and_then(|r| process_execution_result(r))?;
+            .and_then(|(_, r)| process_execution_result(r))?;
 
         match output {
             Output::Create(_, Some(address)) => Ok(address.into()),
@@ -124,11 +845,33 @@ impl RevmClient for BasicClient {
         }
     }
 
+    fn etch(
+        &self,
+        address: Address,
+        code: Bytes,
+        storage: HashMap<U256, U256>,
+    ) -> eyre::Result<()> {
+        BasicClient::etch(self, address, code, storage)
+    }
+
+    fn deploy_with_receipt(&self, tx: TxEnv) -> eyre::Result<(Address, TransactionReceipt)> {
+        let (hash, result) = self
+            .mine_transaction(tx)
+            .map_err(|e| eyre::eyre!("error on deploy: {:?}", e))?;
+        let (output, _, _) = process_execution_result(result)?;
+        let Output::Create(_, Some(address)) = output else {
+            eyre::bail!("expected a create call");
+        };
+        let receipt = self
+            .get_receipt(hash)
+            .expect("mined transaction has a receipt");
+        Ok((address.into(), receipt))
+    }
+
     // This is invoked in contract::call:FunctionCall
     fn call(&self, tx: TxEnv) -> eyre::Result<revm::primitives::Bytes> {
-        self.evm.borrow_mut().env.tx = tx;
-        match self.evm.borrow_mut().transact_ref() {
-            Ok(ResultAndState { result, .. }) => {
+        match self.transact_ref(tx) {
+            Ok(result) => {
                 let (r, _, _) = process_result_with_value(result)?;
                 Ok(r)
             }
@@ -136,14 +879,30 @@ impl RevmClient for BasicClient {
         }
     }
 
+    fn call_at(&self, tx: TxEnv, number: u64) -> eyre::Result<revm::primitives::Bytes> {
+        BasicClient::call_at(self, tx, number)
+    }
+
+    fn call_with_overrides(
+        &self,
+        tx: TxEnv,
+        state: &StateOverride,
+        block: &BlockOverrides,
+    ) -> eyre::Result<revm::primitives::Bytes> {
+        BasicClient::call_with_overrides(self, tx, state, block)
+    }
+
+    fn create_access_list(&self, tx: TxEnv) -> eyre::Result<AccessListWithGas> {
+        BasicClient::create_access_list(self, tx)
+    }
+
     // This is invoked in contract::call:FunctionCall
     fn send_transaction(
         &self,
         tx: TxEnv,
     ) -> eyre::Result<(revm::primitives::Bytes, u64, Vec<RawLog>)> {
-        self.evm.borrow_mut().env.tx = tx;
-        match self.evm.borrow_mut().transact_commit() {
-            Ok(result) => {
+        match self.mine_transaction(tx) {
+            Ok((_, result)) => {
                 let (b, gas, logs) = process_result_with_value(result)?;
                 let rlogs = into_ether_raw_log(logs);
                 Ok((b, gas, rlogs))
@@ -151,6 +910,89 @@ impl RevmClient for BasicClient {
             _ => eyre::bail!("error with write..."),
         }
     }
+
+    // This is invoked in contract::deploy:ContractDeployer
+    fn send_transaction_with_receipt(
+        &self,
+        tx: TxEnv,
+    ) -> eyre::Result<(revm::primitives::Bytes, TransactionReceipt)> {
+        let (hash, result) = self
+            .mine_transaction(tx)
+            .map_err(|e| eyre::eyre!("error on transact: {:?}", e))?;
+        let (output, _, _) = process_result_with_value(result)?;
+        let receipt = self
+            .get_receipt(hash)
+            .expect("mined transaction has a receipt");
+        Ok((output, receipt))
+    }
+
+    // This is invoked in contract::call:FunctionCall
+    fn submit_transaction(&self, tx: TxEnv) -> eyre::Result<H256> {
+        BasicClient::submit_transaction(self, tx)
+    }
+
+    // This is invoked in contract::event:Event
+    fn get_logs(&self, filter: &Filter) -> eyre::Result<Vec<EthersLog>> {
+        Ok(self.history().logs(filter))
+    }
+
+    // This is invoked in contract::event:Event
+    fn subscribe_logs(
+        &self,
+        filter: Filter,
+        callback: LogCallback,
+    ) -> eyre::Result<SubscriptionId> {
+        Ok(BasicClient::subscribe_logs(self, filter, callback))
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) -> eyre::Result<bool> {
+        Ok(BasicClient::unsubscribe(self, id))
+    }
+}
+
+/// Move the block environment on to the next block
+fn advance_block(block: &mut BlockEnv) {
+    block.number += revm::primitives::U256::from(1);
+    block.timestamp += revm::primitives::U256::from(1);
+}
+
+/// Flush the evm's cached state to its store, if it has one
+fn checkpoint(evm: &mut EVM<ClientDB>) -> std::io::Result<()> {
+    let block = state::latest_block_env(&evm.env.block);
+    let db = evm.db().expect("evm db");
+
+    // the layers frozen by forking are written too, once no fork reads them
+    let mut layers = vec![&*db];
+    let store = loop {
+        match &layers[layers.len() - 1].db {
+            ClientBackend::Empty(_) => return Ok(()),
+            ClientBackend::Disk(store) => break store.clone(),
+            ClientBackend::Fork(layer) => {
+                if Arc::strong_count(layer) > 1 {
+                    return Err(std::io::Error::other(
+                        "the state is shared with forks of the client",
+                    ));
+                }
+                layers.push(layer);
+            }
+        }
+    };
+    layers.reverse();
+    store.write_cache(&layers, block)?;
+
+    let mut flushed = CacheDB::new(ClientBackend::Disk(store.clone()));
+    flushed.block_hashes = std::mem::take(&mut db.block_hashes);
+    *db = flushed;
+    store.compact_if_needed()
+}
+
+/// Replace the block hashes in `db` with the recent ones from `history`. The
+/// evm only looks up the last 256 blocks, so older hashes are dropped.
+fn serve_block_hashes(db: &mut ClientDB, history: &History) {
+    db.block_hashes = history
+        .recent_block_hashes()
+        .map(|(number, hash)| (revm::primitives::U256::from(number), hash.into()))
+        .collect();
 }
 
 /// helper to extract results
@@ -167,7 +1009,7 @@ fn process_execution_result(result: ExecutionResult) -> eyre::Result<(Output, u6
     }
 }
 
-fn process_result_with_value(
+pub(crate) fn process_result_with_value(
     result: ExecutionResult,
 ) -> eyre::Result<(revm::primitives::Bytes, u64, Vec<Log>)> {
     let (output, gas_used, logs) = process_execution_result(result)?;
@@ -180,10 +1022,10 @@ fn process_result_with_value(
 }
 
 /// convert revm Logs to ethers RawLog
-fn into_ether_raw_log(logs: Vec<Log>) -> Vec<RawLog> {
+pub(crate) fn into_ether_raw_log(logs: Vec<Log>) -> Vec<RawLog> {
     logs.iter()
         .map(|log| {
-            let topics: Vec<H256> = log.topics.iter().map(|x| x.clone().into()).collect();
+            let topics: Vec<H256> = log.topics.iter().map(|x| (*x).into()).collect();
             RawLog {
                 topics,
                 data: log.clone().data.into(),
@@ -191,3 +1033,364 @@ fn into_ether_raw_log(logs: Vec<Log>) -> Vec<RawLog> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::{types::BlockNumber, utils::parse_ether};
+
+    // init code for a contract that emits `LOG1` with topic `0xaa` whenever it's called
+    const LOGGER: &str = "6008600c60003960086000f360aa60006000a100";
+
+    fn deploy_logger(client: &BasicClient, caller: Address) -> Address {
+        let tx = TxEnv {
+            caller: caller.into(),
+            transact_to: TransactTo::create(),
+            data: hex_bytes(LOGGER),
+            ..Default::default()
+        };
+        client.deploy(tx).unwrap()
+    }
+
+    fn hex_bytes(s: &str) -> revm::primitives::Bytes {
+        s.parse::<Bytes>().unwrap().0
+    }
+
+    #[test]
+    fn minimal_clients_only_implement_the_required_methods() {
+        struct Minimal;
+        impl RevmClient for Minimal {
+            fn deploy(&self, _tx: TxEnv) -> eyre::Result<Address> {
+                Ok(Address::zero())
+            }
+            fn call(&self, _tx: TxEnv) -> eyre::Result<revm::primitives::Bytes> {
+                Ok(Default::default())
+            }
+            fn send_transaction(
+                &self,
+                _tx: TxEnv,
+            ) -> eyre::Result<(revm::primitives::Bytes, u64, Vec<RawLog>)> {
+                Ok(Default::default())
+            }
+        }
+
+        let error = Minimal.call_at(TxEnv::default(), 0).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`call_at` is not supported by this client"
+        );
+        assert!(Minimal.get_logs(&Filter::new()).is_err());
+    }
+
+    #[test]
+    fn etches_code_and_storage() {
+        let client = BasicClient::new();
+        let alice = generate_random_account();
+        let predeploy = client.create_account_with_balance(U256::from(10)).unwrap();
+        let mut storage = HashMap::new();
+        storage.insert(U256::zero(), U256::from(42));
+        // returns its slot 0
+        let code: Bytes = "0x60005460005260206000f3".parse().unwrap();
+        client.etch(predeploy, code.clone(), storage).unwrap();
+
+        assert_eq!(client.get_code(predeploy), code);
+        assert_eq!(client.get_balance(predeploy), U256::from(10));
+        let output = client
+            .call(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::Call(predeploy.into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&output), U256::from(42));
+    }
+
+    #[test]
+    fn serves_recent_block_hashes() {
+        // returns `blockhash` of the block number passed as calldata
+        const BLOCK_HASH: &str = "600c600c600039600c6000f36000354060005260206000f3";
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::zero()).unwrap();
+        let contract = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: hex_bytes(BLOCK_HASH),
+                ..Default::default()
+            })
+            .unwrap();
+        let block_hash = |number: u64| {
+            let output = client
+                .call(TxEnv {
+                    caller: alice.into(),
+                    transact_to: TransactTo::Call(contract.into()),
+                    data: H256::from_low_u64_be(number).0.to_vec().into(),
+                    ..Default::default()
+                })
+                .unwrap();
+            H256::from_slice(&output)
+        };
+
+        assert_eq!(block_hash(1), client.get_block(1).unwrap().hash.unwrap());
+        assert_eq!(block_hash(0), client.get_block(0).unwrap().hash.unwrap());
+        // the block being built has no hash yet
+        assert_eq!(block_hash(2), H256::zero());
+
+        let fixed = H256::repeat_byte(0x11);
+        client.set_block_hash(1, fixed);
+        client.set_block_hash(2, fixed);
+        assert_eq!(block_hash(1), fixed);
+        client.open_block().seal();
+        assert_eq!(block_hash(2), fixed);
+        assert_ne!(client.get_block(2).unwrap().hash, Some(fixed));
+        client.clear_block_hash(1);
+        assert_eq!(block_hash(1), client.get_block(1).unwrap().hash.unwrap());
+
+        for _ in 0..256 {
+            client.open_block().seal();
+        }
+        assert_eq!(client.block_number(), U256::from(258));
+        assert_eq!(block_hash(2), H256::zero());
+        assert_eq!(block_hash(3), client.get_block(3).unwrap().hash.unwrap());
+    }
+
+    #[test]
+    fn records_history_and_logs() {
+        let client = BasicClient::new();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        assert_eq!(client.block_number(), U256::zero());
+
+        let logger = deploy_logger(&client, alice);
+        assert_eq!(client.block_number(), U256::one());
+
+        let call = TxEnv {
+            caller: alice.into(),
+            transact_to: TransactTo::Call(logger.into()),
+            ..Default::default()
+        };
+        client.send_transaction(call.clone()).unwrap();
+        client.send_transaction(call).unwrap();
+        assert_eq!(client.block_number(), U256::from(3));
+
+        let block = client.get_block(2).unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        let receipt = client.get_receipt(block.transactions[0]).unwrap();
+        assert_eq!(receipt.status, Some(1u64.into()));
+        assert_eq!(receipt.logs.len(), 1);
+        let tx = client.get_transaction(block.transactions[0]).unwrap();
+        assert_eq!(tx.nonce, U256::one());
+        assert_eq!(tx.to, Some(logger));
+
+        let filter = Filter::new()
+            .address(logger)
+            .topic0(H256::from_low_u64_be(0xaa))
+            .from_block(BlockNumber::Earliest);
+        assert_eq!(client.get_logs(&filter).len(), 2);
+        assert_eq!(client.get_logs(&filter.clone().to_block(2)).len(), 1);
+        assert!(client
+            .get_logs(&filter.topic0(H256::from_low_u64_be(0xbb)))
+            .is_empty());
+    }
+
+    #[test]
+    fn notifies_log_subscribers() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let client = Arc::new(BasicClient::new());
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let logger = deploy_logger(&client, alice);
+
+        let (id, receiver) = client.subscribe_logs_channel(Filter::new().address(logger));
+
+        // callbacks may use the client
+        let seen = Arc::new(AtomicUsize::new(0));
+        let (inner, counter) = (client.clone(), seen.clone());
+        client.subscribe_logs(
+            Filter::new().topic0(H256::from_low_u64_be(0xaa)),
+            move |log| {
+                assert!(inner.get_receipt(log.transaction_hash.unwrap()).is_some());
+                counter.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+        let unmatched = Arc::new(AtomicUsize::new(0));
+        let counter = unmatched.clone();
+        client.subscribe_logs(
+            Filter::new().topic0(H256::from_low_u64_be(0xbb)),
+            move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+
+        let call = TxEnv {
+            caller: alice.into(),
+            transact_to: TransactTo::Call(logger.into()),
+            ..Default::default()
+        };
+        client.send_transaction(call.clone()).unwrap();
+        client.send_transaction(call.clone()).unwrap();
+
+        let logs = receiver.try_iter().collect::<Vec<_>>();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].block_number, Some(3u64.into()));
+        assert_eq!(seen.load(Ordering::SeqCst), 2);
+        assert_eq!(unmatched.load(Ordering::SeqCst), 0);
+
+        assert!(client.unsubscribe(id));
+        assert!(!client.unsubscribe(id));
+        client.send_transaction(call).unwrap();
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(seen.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn runs_inspectors() {
+        use revm::{
+            interpreter::{InstructionResult, Interpreter},
+            primitives::{Bytes as RevmBytes, B160, B256},
+            EVMData, Inspector,
+        };
+
+        #[derive(Default)]
+        struct Counter {
+            steps: usize,
+            logs: usize,
+        }
+
+        impl Inspector<ClientDB> for Counter {
+            fn step(
+                &mut self,
+                _interp: &mut Interpreter,
+                _data: &mut EVMData<'_, ClientDB>,
+                _is_static: bool,
+            ) -> InstructionResult {
+                self.steps += 1;
+                InstructionResult::Continue
+            }
+
+            fn log(
+                &mut self,
+                _evm_data: &mut EVMData<'_, ClientDB>,
+                _address: &B160,
+                _topics: &[B256],
+                _data: &RevmBytes,
+            ) {
+                self.logs += 1;
+            }
+        }
+
+        let client = BasicClient::new();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+
+        // the logger's init code runs 7 opcodes, its runtime code 5
+        let (logger, deployed) =
+            client.inspect(Counter::default(), || deploy_logger(&client, alice));
+        assert_eq!((deployed.steps, deployed.logs), (7, 0));
+
+        let id = client.add_inspector(Counter::default());
+        let call = TxEnv {
+            caller: alice.into(),
+            transact_to: TransactTo::Call(logger.into()),
+            ..Default::default()
+        };
+        client.call(call.clone()).unwrap();
+        let ((), called) = client.inspect(Counter::default(), || {
+            client.send_transaction(call.clone()).unwrap();
+        });
+        assert_eq!((called.steps, called.logs), (5, 1));
+
+        let registered = client
+            .remove_inspector(id)
+            .and_then(|inspector| inspector.into_any().downcast::<Counter>().ok())
+            .unwrap();
+        assert_eq!((registered.steps, registered.logs), (10, 2));
+        assert!(client.remove_inspector(id).is_none());
+    }
+
+    #[test]
+    fn calls_while_sending_transactions() {
+        use std::{sync::mpsc, thread, time::Duration};
+
+        let client = Arc::new(BasicClient::new());
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let logger = deploy_logger(&client, alice);
+        let call = TxEnv {
+            caller: alice.into(),
+            transact_to: TransactTo::Call(logger.into()),
+            ..Default::default()
+        };
+
+        let (done, finished) = mpsc::channel();
+        for send in [false, true, false, true] {
+            let (client, call, done) = (client.clone(), call.clone(), done.clone());
+            thread::spawn(move || {
+                for _ in 0..2_000 {
+                    if send {
+                        client.send_transaction(call.clone()).unwrap();
+                    } else {
+                        client.call(call.clone()).unwrap();
+                    }
+                }
+                done.send(()).unwrap();
+            });
+        }
+        for _ in 0..4 {
+            finished
+                .recv_timeout(Duration::from_secs(30))
+                .expect("calls and transactions deadlocked");
+        }
+        assert_eq!(client.block_number(), 4_001.into());
+    }
+
+    #[test]
+    fn charges_eip1559_fees() {
+        let gwei = U256::exp10(9);
+        let coinbase = generate_random_account();
+        let client = ClientBuilder::new()
+            .block_gas_limit(30_000_000.into())
+            .base_fee(gwei)
+            .priority_fee(gwei * 2)
+            .coinbase(coinbase)
+            .build();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let bob = generate_random_account();
+
+        client.transfer(bob, alice, 1.into()).unwrap();
+        let gas = U256::from(21_000);
+        assert_eq!(
+            client.get_balance(alice),
+            parse_ether(1u8).unwrap() - 1 - gas * gwei * 3
+        );
+        assert_eq!(client.get_balance(bob), 1.into());
+        // the base fee is burned and the tip paid to the coinbase
+        assert_eq!(client.get_balance(coinbase), gas * gwei * 2);
+
+        // 21000 gas is below the 15M target, so the base fee drops
+        let target = U256::from(15_000_000);
+        let expected = gwei - gwei * (target - gas) / target / 8;
+        assert_eq!(U256::from(client.block_env().basefee), expected);
+        let block = client.get_block(1).unwrap();
+        assert_eq!(block.base_fee_per_gas, Some(gwei));
+        assert_eq!(block.author, Some(coinbase));
+
+        // calls without a gas price are not checked against the base fee
+        let call = TxEnv {
+            caller: bob.into(),
+            transact_to: TransactTo::Call(alice.into()),
+            ..Default::default()
+        };
+        assert!(client.call(call).is_ok());
+    }
+}
@@ -0,0 +1,319 @@
+//!
+//! Library linking: patching the `__$<hash>$__` placeholders solc leaves in bytecode
+//! with the addresses of deployed libraries
+//!
+use ethers_core::types::{Address, Bytes};
+use revm::primitives::{TransactTo, TxEnv};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{BasicClient, RevmClient};
+
+/// Byte offsets of the placeholders in bytecode, by library name
+pub type LinkReferences = BTreeMap<String, Vec<usize>>;
+
+/// The length of a placeholder: the 20 bytes of an address, 40 hex characters
+const PLACEHOLDER_LEN: usize = 20;
+
+/// Bytecode that may need library addresses before it can be deployed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnlinkedBytecode {
+    /// The bytecode, with zeros in place of the placeholders
+    pub bytecode: Bytes,
+    pub link_references: LinkReferences,
+}
+
+impl UnlinkedBytecode {
+    /// Read the creation bytecode and its `linkReferences` from a compiler artifact,
+    /// in the Hardhat, Foundry or solc standard-json output shape. Truffle artifacts
+    /// have no `linkReferences`, but name the libraries in their placeholders. Returns
+    /// `None` if the artifact has no bytecode.
+    pub fn from_artifact(artifact: &str) -> eyre::Result<Option<Self>> {
+        let artifact: Value = serde_json::from_str(artifact)?;
+        let bytecode = artifact
+            .get("bytecode")
+            .or_else(|| artifact.pointer("/evm/bytecode"));
+        let (object, references) = match bytecode {
+            Some(Value::String(object)) => (object, artifact.get("linkReferences")),
+            Some(Value::Object(bytecode)) => match bytecode.get("object") {
+                Some(Value::String(object)) => (object, bytecode.get("linkReferences")),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        let link_references = match references {
+            Some(references) => parse_link_references(references)?,
+            None => named_placeholders(object),
+        };
+        let code = Self::from_hex(object, link_references)?;
+        Ok((!code.bytecode.is_empty()).then_some(code))
+    }
+
+    /// Decode hex `bytecode`, zeroing the placeholders at `link_references`
+    pub fn from_hex(bytecode: &str, link_references: LinkReferences) -> eyre::Result<Self> {
+        let mut hex = bytecode.trim_start_matches("0x").to_string();
+        for offsets in link_references.values() {
+            for offset in offsets {
+                let range = offset * 2..(offset + PLACEHOLDER_LEN) * 2;
+                if range.end > hex.len() {
+                    eyre::bail!("link reference at {offset} is outside the bytecode");
+                }
+                hex.replace_range(range, &"0".repeat(PLACEHOLDER_LEN * 2));
+            }
+        }
+        if let Some(index) = hex.find("__") {
+            eyre::bail!(
+                "bytecode has a library placeholder at byte {} without a link reference",
+                index / 2
+            );
+        }
+        let bytecode = hex.parse::<Bytes>()?;
+        Ok(Self {
+            bytecode,
+            link_references,
+        })
+    }
+
+    /// The names of the libraries the bytecode needs
+    pub fn libraries(&self) -> impl Iterator<Item = &str> {
+        self.link_references.keys().map(String::as_str)
+    }
+
+    /// The bytecode with the addresses of the `libraries` it references
+    pub fn link(&self, libraries: &HashMap<String, Address>) -> eyre::Result<Bytes> {
+        link_bytecode(&self.bytecode, &self.link_references, libraries)
+    }
+}
+
+/// Parse the `linkReferences` of an artifact, which map source files to the
+/// libraries they define and their placeholders:
+/// `{ "<file>": { "<library>": [{ "start": <offset>, "length": 20 }] } }`
+///
+/// Libraries are known by name, so two libraries with the same name in different
+/// files are an error.
+pub fn parse_link_references(references: &Value) -> eyre::Result<LinkReferences> {
+    let mut parsed = LinkReferences::new();
+    let mut files_by_name: HashMap<&str, &str> = HashMap::new();
+    let Some(files) = references.as_object() else {
+        eyre::bail!("link references should be an object of source files");
+    };
+    for (file, libraries) in files {
+        let Some(libraries) = libraries.as_object() else {
+            eyre::bail!("link references should map source files to libraries");
+        };
+        for (name, placeholders) in libraries {
+            if let Some(other) = files_by_name.insert(name, file) {
+                eyre::bail!("library {name} is linked from both {other} and {file}");
+            }
+            let offsets = parsed.entry(name.clone()).or_default();
+            for placeholder in placeholders.as_array().into_iter().flatten() {
+                let start = placeholder.get("start").and_then(Value::as_u64);
+                let length = placeholder.get("length").and_then(Value::as_u64);
+                match (start, length) {
+                    (Some(start), Some(length)) if length as usize == PLACEHOLDER_LEN => {
+                        offsets.push(start as usize)
+                    }
+                    _ => eyre::bail!("invalid link reference for {name}: {placeholder}"),
+                }
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Find the placeholders of Truffle and solc before 0.5, `__<Library>___...`, which
+/// name the library rather than hash it
+fn named_placeholders(bytecode: &str) -> LinkReferences {
+    let hex = bytecode.trim_start_matches("0x");
+    let mut references = LinkReferences::new();
+    let mut index = 0;
+    while let Some(found) = hex[index..].find("__") {
+        let start = index + found;
+        let Some(placeholder) = hex.get(start..start + PLACEHOLDER_LEN * 2) else {
+            break;
+        };
+        // older solc names the library by its source, `<file>:<library>`
+        let name = placeholder
+            .trim_matches('_')
+            .rsplit(':')
+            .next()
+            .unwrap_or_default();
+        // anything else is left for `from_hex` to report
+        if start % 2 != 0 || name.is_empty() || name.starts_with('$') {
+            break;
+        }
+        references
+            .entry(name.to_string())
+            .or_default()
+            .push(start / 2);
+        index = start + PLACEHOLDER_LEN * 2;
+    }
+    references
+}
+
+/// Patch `bytecode` with the address of each library at its `link_references`
+pub fn link_bytecode(
+    bytecode: &[u8],
+    link_references: &LinkReferences,
+    libraries: &HashMap<String, Address>,
+) -> eyre::Result<Bytes> {
+    let mut linked = bytecode.to_vec();
+    for (name, offsets) in link_references {
+        let Some(address) = libraries.get(name) else {
+            eyre::bail!("missing the address of library {name}");
+        };
+        for offset in offsets {
+            let Some(placeholder) = linked.get_mut(*offset..offset + PLACEHOLDER_LEN) else {
+                eyre::bail!("link reference at {offset} is outside the bytecode");
+            };
+            placeholder.copy_from_slice(address.as_bytes());
+        }
+    }
+    Ok(linked.into())
+}
+
+impl BasicClient {
+    /// Deploy `libraries` from `caller`, each after the libraries it links to, and
+    /// return their addresses by name. Libraries already in `deployed` are linked
+    /// to but not deployed again.
+    pub fn deploy_libraries(
+        &self,
+        caller: Address,
+        libraries: &BTreeMap<String, UnlinkedBytecode>,
+        mut deployed: HashMap<String, Address>,
+    ) -> eyre::Result<HashMap<String, Address>> {
+        let mut pending: Vec<_> = libraries
+            .iter()
+            .filter(|(name, _)| !deployed.contains_key(*name))
+            .collect();
+        while !pending.is_empty() {
+            let (ready, waiting): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|(_, code)| code.libraries().all(|lib| deployed.contains_key(lib)));
+            if ready.is_empty() {
+                let (name, code) = waiting[0];
+                let missing = code
+                    .libraries()
+                    .find(|lib| !deployed.contains_key(*lib))
+                    .unwrap_or_default();
+                if libraries.contains_key(missing) {
+                    eyre::bail!(
+                        "library {name} links to {missing}, and the libraries link in a cycle"
+                    );
+                }
+                eyre::bail!("library {name} links to {missing}, which isn't given");
+            }
+            for (name, code) in ready {
+                let address = self.deploy(TxEnv {
+                    caller: caller.into(),
+                    transact_to: TransactTo::create(),
+                    data: code.link(&deployed)?.0,
+                    ..Default::default()
+                })?;
+                deployed.insert(name.clone(), address);
+            }
+            pending = waiting;
+        }
+        Ok(deployed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::types::U256;
+
+    // returns runtime code `0x00`
+    const LIBRARY: &str = "0x60016000f3";
+
+    // pushes the address at byte 7 and returns its code size
+    fn caller_of(placeholder: &str) -> String {
+        format!("0x601e600c600039601e6000f373{placeholder}3b60005260206000f3")
+    }
+
+    fn references(library: &str) -> LinkReferences {
+        LinkReferences::from([(library.to_string(), vec![13])])
+    }
+
+    #[test]
+    fn reads_link_references_from_artifacts() {
+        let placeholder = "__$a1b2c3d4e5f60718293a4b5c6d7e8f9012$__";
+        let refs = r#"{"contracts/Math.sol": {"Math": [{"start": 13, "length": 20}]}}"#;
+        let hardhat = format!(
+            r#"{{"abi": [], "bytecode": "{}", "linkReferences": {refs}}}"#,
+            caller_of(placeholder)
+        );
+        let foundry = format!(
+            r#"{{"abi": [], "bytecode": {{"object": "{}", "linkReferences": {refs}}}}}"#,
+            caller_of(placeholder)
+        );
+        let solc = format!(
+            r#"{{"abi": [], "evm": {{"bytecode": {{"object": "{}", "linkReferences": {refs}}}}}}}"#,
+            caller_of(placeholder)
+        );
+        let zeroed = caller_of(&"0".repeat(40)).parse::<Bytes>().unwrap();
+        for artifact in [hardhat, foundry, solc] {
+            let code = UnlinkedBytecode::from_artifact(&artifact).unwrap().unwrap();
+            assert_eq!(code.bytecode, zeroed);
+            assert_eq!(code.link_references, references("Math"));
+        }
+
+        // Truffle names the library in the placeholder
+        let truffle = format!(
+            r#"{{"contractName": "Calc", "abi": [], "bytecode": "{}"}}"#,
+            caller_of(&format!("__Math{}", "_".repeat(34)))
+        );
+        let code = UnlinkedBytecode::from_artifact(&truffle).unwrap().unwrap();
+        assert_eq!(code.bytecode, zeroed);
+        assert_eq!(code.link_references, references("Math"));
+
+        let unreferenced = format!(r#"{{"abi": [], "bytecode": "{}"}}"#, caller_of(placeholder));
+        assert!(UnlinkedBytecode::from_artifact(&unreferenced).is_err());
+        let empty = r#"{"abi": [], "bytecode": "0x"}"#;
+        assert!(UnlinkedBytecode::from_artifact(empty).unwrap().is_none());
+
+        // same-named libraries from different files can't be told apart
+        let ambiguous: Value = serde_json::from_str(
+            r#"{"a/Math.sol": {"Math": [{"start": 13, "length": 20}]},
+                "b/Math.sol": {"Math": [{"start": 40, "length": 20}]}}"#,
+        )
+        .unwrap();
+        assert!(parse_link_references(&ambiguous).is_err());
+    }
+
+    #[test]
+    fn deploys_libraries_in_order() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let zeros = "0".repeat(40);
+        let mut libraries = BTreeMap::new();
+        // `Outer` links to `Inner`, so is deployed after it despite sorting first
+        libraries.insert(
+            "Outer".to_string(),
+            UnlinkedBytecode::from_hex(&caller_of(&zeros), references("Inner")).unwrap(),
+        );
+        libraries.insert(
+            "Inner".to_string(),
+            UnlinkedBytecode::from_hex(LIBRARY, LinkReferences::new()).unwrap(),
+        );
+        let deployed = client
+            .deploy_libraries(alice, &libraries, HashMap::new())
+            .unwrap();
+
+        let output = client
+            .call(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::Call(deployed["Outer"].into()),
+                ..Default::default()
+            })
+            .unwrap();
+        // the size of `Inner`'s code
+        assert_eq!(U256::from_big_endian(&output), U256::one());
+
+        let missing = BTreeMap::from([("Outer".to_string(), libraries["Outer"].clone())]);
+        assert!(client
+            .deploy_libraries(alice, &missing, HashMap::new())
+            .is_err());
+        assert!(client.deploy_libraries(alice, &missing, deployed).is_ok());
+    }
+}
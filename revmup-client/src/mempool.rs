@@ -0,0 +1,353 @@
+//!
+//! Pool of pending transactions, waiting to be mined
+//!
+use ethers_core::types::H256;
+use revm::primitives::{TxEnv, B160, U256};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+/// Compares two pending transactions. `Ordering::Less` mines `a` first
+pub type TxComparator =
+    Arc<dyn Fn(&PendingTransaction, &PendingTransaction) -> Ordering + Send + Sync>;
+
+/// The order pending transactions are mined in.
+///
+/// Whatever the policy, each sender's transactions are mined in nonce order.
+/// Ties are broken by the order the transactions were submitted.
+#[derive(Clone, Default)]
+pub enum TxOrdering {
+    /// In the order they were submitted
+    #[default]
+    Fifo,
+    /// Highest effective priority fee first
+    PriorityFee,
+    /// By a user supplied comparator
+    Custom(TxComparator),
+}
+
+impl fmt::Debug for TxOrdering {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fifo => f.write_str("Fifo"),
+            Self::PriorityFee => f.write_str("PriorityFee"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// A transaction waiting in the pool
+#[derive(Debug, Clone)]
+pub struct PendingTransaction {
+    /// The transaction hash
+    pub hash: H256,
+    /// The transaction, with its nonce set
+    pub tx: TxEnv,
+    /// The tip the transaction pays per gas at the base fee of the block being built
+    pub priority_fee: U256,
+    /// Position in submission order
+    pub(crate) sequence: u64,
+}
+
+impl PendingTransaction {
+    /// The account sending the transaction, `tx.caller`
+    pub fn sender(&self) -> B160 {
+        self.tx.caller
+    }
+
+    /// The nonce the transaction was pooled with, always set on `tx`
+    pub fn nonce(&self) -> u64 {
+        self.tx.nonce.unwrap_or_default()
+    }
+
+    /// Position in submission order, lower was submitted first
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// Pending transactions by sender and nonce
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Mempool {
+    pub(crate) ordering: TxOrdering,
+    senders: HashMap<B160, BTreeMap<u64, PendingTransaction>>,
+    next_sequence: u64,
+}
+
+/// Replacements must raise both fees by this percentage
+const REPLACEMENT_BUMP: u64 = 10;
+
+impl Mempool {
+    pub(crate) fn new(ordering: TxOrdering) -> Self {
+        Self {
+            ordering,
+            ..Default::default()
+        }
+    }
+
+    /// The next free nonce of `sender`, given its nonce in the db: the first one
+    /// without a pending transaction
+    pub(crate) fn next_nonce(&self, sender: B160, state_nonce: u64) -> u64 {
+        let mut nonce = state_nonce;
+        if let Some(txs) = self.senders.get(&sender) {
+            while txs.contains_key(&nonce) {
+                nonce += 1;
+            }
+        }
+        nonce
+    }
+
+    /// Add `tx`, which must have its nonce set. A transaction with the same sender
+    /// and nonce as a pending one replaces it if it raises both the max fee and the
+    /// priority fee by at least 10%.
+    pub(crate) fn insert(&mut self, hash: H256, tx: TxEnv) -> eyre::Result<()> {
+        let nonce = tx.nonce.expect("pooled transactions have a nonce");
+        let txs = self.senders.entry(tx.caller).or_default();
+        if let Some(pending) = txs.get(&nonce) {
+            let bumped = |old: U256, new: U256| {
+                new.saturating_mul(U256::from(100))
+                    >= old.saturating_mul(U256::from(100 + REPLACEMENT_BUMP))
+            };
+            let fee = |tx: &TxEnv| tx.gas_priority_fee.unwrap_or(tx.gas_price);
+            if !(bumped(pending.tx.gas_price, tx.gas_price) && bumped(fee(&pending.tx), fee(&tx))) {
+                eyre::bail!("replacement transaction underpriced");
+            }
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        txs.insert(
+            nonce,
+            PendingTransaction {
+                hash,
+                tx,
+                priority_fee: U256::ZERO,
+                sequence,
+            },
+        );
+        Ok(())
+    }
+
+    /// Put back transactions taken by `take_ordered`, keeping their place in the
+    /// submission order
+    pub(crate) fn restore(&mut self, pending: Vec<PendingTransaction>) {
+        for pending in pending {
+            self.senders
+                .entry(pending.sender())
+                .or_default()
+                .entry(pending.nonce())
+                .or_insert(pending);
+        }
+    }
+
+    /// All pending transactions, in submission order
+    pub(crate) fn pending(&self, tip: impl Fn(&TxEnv) -> U256) -> Vec<PendingTransaction> {
+        let mut pending = self
+            .senders
+            .values()
+            .flat_map(|txs| txs.values().cloned())
+            .map(|mut pending| {
+                pending.priority_fee = tip(&pending.tx);
+                pending
+            })
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|pending| pending.sequence);
+        pending
+    }
+
+    /// Take the executable transactions out of the pool, ordered by the policy.
+    ///
+    /// A sender's transactions are executable from its db nonce without gaps.
+    /// Transactions with a nonce below it are dropped, later ones stay pooled.
+    pub(crate) fn take_ordered(
+        &mut self,
+        state_nonce: impl Fn(B160) -> u64,
+        tip: impl Fn(&TxEnv) -> U256,
+    ) -> Vec<PendingTransaction> {
+        let mut queues = Vec::new();
+        for (sender, txs) in self.senders.iter_mut() {
+            let mut nonce = state_nonce(*sender);
+            txs.retain(|n, _| *n >= nonce);
+            let mut queue = Vec::new();
+            while let Some(mut pending) = txs.remove(&nonce) {
+                pending.priority_fee = tip(&pending.tx);
+                queue.push(pending);
+                nonce += 1;
+            }
+            if !queue.is_empty() {
+                queue.reverse();
+                queues.push(queue);
+            }
+        }
+        self.senders.retain(|_, txs| !txs.is_empty());
+
+        // repeatedly take the best of the senders' next transactions
+        let mut ordered = Vec::new();
+        while let Some(best) = (0..queues.len()).min_by(|a, b| {
+            let (a, b) = (queues[*a].last().unwrap(), queues[*b].last().unwrap());
+            self.compare(a, b).then(a.sequence.cmp(&b.sequence))
+        }) {
+            ordered.push(queues[best].pop().unwrap());
+            if queues[best].is_empty() {
+                queues.swap_remove(best);
+            }
+        }
+        ordered
+    }
+
+    fn compare(&self, a: &PendingTransaction, b: &PendingTransaction) -> Ordering {
+        match &self.ordering {
+            TxOrdering::Fifo => Ordering::Equal,
+            TxOrdering::PriorityFee => b.priority_fee.cmp(&a.priority_fee),
+            TxOrdering::Custom(compare) => compare(a, b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_random_account, BasicClient, ClientBuilder};
+    use ethers_core::{types::Address, utils::parse_ether};
+    use revm::primitives::TransactTo;
+
+    fn transfer(from: Address, priority_fee: u64) -> TxEnv {
+        TxEnv {
+            caller: from.into(),
+            transact_to: TransactTo::Call(generate_random_account().into()),
+            gas_limit: 21_000,
+            gas_price: U256::from(100 + priority_fee),
+            gas_priority_fee: Some(U256::from(priority_fee)),
+            ..Default::default()
+        }
+    }
+
+    fn funded(client: &BasicClient) -> Address {
+        client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap()
+    }
+
+    fn mined_hashes(client: &BasicClient) -> Vec<H256> {
+        client
+            .mine()
+            .receipts
+            .iter()
+            .map(|receipt| receipt.transaction_hash)
+            .collect()
+    }
+
+    #[test]
+    fn mines_in_submission_and_nonce_order() {
+        let client = BasicClient::new();
+        let (alice, bob, carol) = (funded(&client), funded(&client), funded(&client));
+
+        let a0 = client.submit_transaction(transfer(alice, 0)).unwrap();
+        let b0 = client.submit_transaction(transfer(bob, 0)).unwrap();
+        let a1 = client.submit_transaction(transfer(alice, 0)).unwrap();
+        // carol's nonce 1 waits for her nonce 0
+        let c1 = client
+            .submit_transaction(TxEnv {
+                nonce: Some(1),
+                ..transfer(carol, 0)
+            })
+            .unwrap();
+        assert_eq!(client.pending_transactions().len(), 4);
+
+        assert_eq!(mined_hashes(&client), vec![a0, b0, a1]);
+        assert_eq!(client.get_nonce(alice), 2);
+        assert_eq!(client.pending_transactions()[0].hash, c1);
+
+        let c0 = client.submit_transaction(transfer(carol, 0)).unwrap();
+        assert_eq!(mined_hashes(&client), vec![c0, c1]);
+        assert!(client.pending_transactions().is_empty());
+    }
+
+    #[test]
+    fn orders_by_priority_fee_and_comparator() {
+        let client = ClientBuilder::new()
+            .base_fee(100.into())
+            .ordering(TxOrdering::PriorityFee)
+            .build();
+        let (alice, bob) = (funded(&client), funded(&client));
+
+        let a0 = client.submit_transaction(transfer(alice, 1)).unwrap();
+        let a1 = client.submit_transaction(transfer(alice, 5)).unwrap();
+        let b0 = client.submit_transaction(transfer(bob, 3)).unwrap();
+        // alice's higher tip can't jump her own earlier transaction
+        assert_eq!(mined_hashes(&client), vec![b0, a0, a1]);
+
+        // last in, first out
+        client.set_ordering(TxOrdering::Custom(Arc::new(|a, b| {
+            b.sequence().cmp(&a.sequence())
+        })));
+        let a2 = client.submit_transaction(transfer(alice, 1)).unwrap();
+        let b1 = client.submit_transaction(transfer(bob, 1)).unwrap();
+        assert_eq!(mined_hashes(&client), vec![b1, a2]);
+    }
+
+    #[test]
+    fn replaces_by_fee() {
+        let client = BasicClient::new();
+        let alice = funded(&client);
+
+        client.submit_transaction(transfer(alice, 10)).unwrap();
+        let underpriced = TxEnv {
+            nonce: Some(0),
+            ..transfer(alice, 10)
+        };
+        assert!(client.submit_transaction(underpriced).is_err());
+
+        let replacement = client
+            .submit_transaction(TxEnv {
+                nonce: Some(0),
+                gas_price: U256::from(200),
+                gas_priority_fee: Some(U256::from(11)),
+                ..transfer(alice, 0)
+            })
+            .unwrap();
+        let pending = client.pending_transactions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].hash, replacement);
+        assert_eq!(mined_hashes(&client), vec![replacement]);
+    }
+
+    #[test]
+    fn keeps_transactions_that_do_not_fit() {
+        let client = ClientBuilder::new().block_gas_limit(50_000.into()).build();
+        let (alice, bob) = (funded(&client), funded(&client));
+
+        let a0 = client.submit_transaction(transfer(alice, 0)).unwrap();
+        let b0 = client.submit_transaction(transfer(bob, 0)).unwrap();
+        let a1 = client.submit_transaction(transfer(alice, 0)).unwrap();
+
+        assert_eq!(mined_hashes(&client), vec![a0, b0]);
+        assert_eq!(client.pending_transactions()[0].hash, a1);
+        assert_eq!(mined_hashes(&client), vec![a1]);
+    }
+
+    #[test]
+    fn keeps_transactions_after_a_rejected_one() {
+        let client = BasicClient::new();
+        let alice = funded(&client);
+
+        // more than alice has
+        client
+            .submit_transaction(TxEnv {
+                value: parse_ether(2u8).unwrap().into(),
+                ..transfer(alice, 0)
+            })
+            .unwrap();
+        let a1 = client.submit_transaction(transfer(alice, 0)).unwrap();
+        let sealed = client.mine();
+        assert_eq!(sealed.rejected.len(), 1);
+        assert!(sealed.receipts.is_empty());
+        assert_eq!(client.pending_transactions()[0].hash, a1);
+
+        let a0 = client.submit_transaction(transfer(alice, 0)).unwrap();
+        assert_eq!(mined_hashes(&client), vec![a0, a1]);
+    }
+}
@@ -0,0 +1,315 @@
+//!
+//! State and block overrides for read-only calls, in the shape of geth's `eth_call`
+//! override sets
+//!
+use ethers_core::types::{Address, Bytes, H256, U256, U64};
+use revm::{
+    db::DatabaseRef,
+    primitives::{AccountInfo, Bytecode, ExecutionResult, TxEnv, B160, B256, U256 as RevmU256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{archive::block_env, BasicClient};
+
+/// Account overrides by address
+pub type StateOverride = HashMap<Address, AccountOverride>;
+
+/// What a call sees of an account instead of its actual state. Unset fields keep
+/// their actual values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// Replaces all of the account's storage: slots not listed are zero
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<HashMap<H256, H256>>,
+    /// Replaces only the slots listed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<HashMap<H256, H256>>,
+}
+
+/// What a call sees of the block it executes in instead of the actual block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub number: Option<U256>,
+    #[serde(default, rename = "time", skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_fee: Option<U256>,
+}
+
+/// A database with some of its accounts overridden
+struct OverrideDB<'a, D> {
+    db: &'a D,
+    accounts: HashMap<B160, OverriddenAccount>,
+}
+
+struct OverriddenAccount {
+    balance: Option<RevmU256>,
+    nonce: Option<u64>,
+    code: Option<Bytecode>,
+    /// Whether slots not in `storage` are zero rather than the actual values
+    cleared: bool,
+    storage: HashMap<RevmU256, RevmU256>,
+}
+
+impl<'a, D> OverrideDB<'a, D> {
+    fn new(db: &'a D, state: &StateOverride) -> eyre::Result<Self> {
+        let mut accounts = HashMap::new();
+        for (address, account) in state {
+            if account.state.is_some() && account.state_diff.is_some() {
+                eyre::bail!("account {address:?} has both state and stateDiff overrides");
+            }
+            let slots = account.state.iter().chain(&account.state_diff).flatten();
+            let storage = slots
+                .map(|(slot, value)| {
+                    let slot = U256::from_big_endian(slot.as_bytes());
+                    let value = U256::from_big_endian(value.as_bytes());
+                    (slot.into(), value.into())
+                })
+                .collect();
+            let overridden = OverriddenAccount {
+                balance: account.balance.map(Into::into),
+                nonce: account.nonce.map(|nonce| nonce.as_u64()),
+                code: account
+                    .code
+                    .as_ref()
+                    .map(|code| Bytecode::new_raw(code.0.clone())),
+                cleared: account.state.is_some(),
+                storage,
+            };
+            accounts.insert((*address).into(), overridden);
+        }
+        Ok(Self { db, accounts })
+    }
+}
+
+impl<D: DatabaseRef> DatabaseRef for OverrideDB<'_, D> {
+    type Error = D::Error;
+
+    fn basic(&self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        let info = self.db.basic(address)?;
+        let Some(account) = self.accounts.get(&address) else {
+            return Ok(info);
+        };
+        let mut info = info.unwrap_or_default();
+        if let Some(balance) = account.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = account.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = &account.code {
+            info.code_hash = code.hash();
+            info.code = Some(code.clone());
+        }
+        Ok(Some(info))
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let overridden = self
+            .accounts
+            .values()
+            .filter_map(|account| account.code.as_ref())
+            .find(|code| code.hash() == code_hash);
+        match overridden {
+            Some(code) => Ok(code.clone()),
+            None => self.db.code_by_hash(code_hash),
+        }
+    }
+
+    fn storage(&self, address: B160, index: RevmU256) -> Result<RevmU256, Self::Error> {
+        match self.accounts.get(&address) {
+            Some(account) => match account.storage.get(&index) {
+                Some(value) => Ok(*value),
+                None if account.cleared => Ok(RevmU256::ZERO),
+                None => self.db.storage(address, index),
+            },
+            None => self.db.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: RevmU256) -> Result<B256, Self::Error> {
+        self.db.block_hash(number)
+    }
+}
+
+impl BasicClient {
+    /// Make a read-only call that sees the accounts in `state` and the block in
+    /// `block` as overridden. The overrides only apply to this call, and
+    /// registered inspectors don't run.
+    pub fn call_with_overrides(
+        &self,
+        tx: TxEnv,
+        state: &StateOverride,
+        block: &BlockOverrides,
+    ) -> eyre::Result<revm::primitives::Bytes> {
+        let result = self.transact_with_overrides(tx, None, state, block)?;
+        let (output, _, _) = crate::process_result_with_value(result)?;
+        Ok(output)
+    }
+
+    /// Execute `tx` without committing it, with overrides on the pending block or,
+    /// given its `number`, on a mined one
+    pub(crate) fn transact_with_overrides(
+        &self,
+        tx: TxEnv,
+        number: Option<u64>,
+        state: &StateOverride,
+        block: &BlockOverrides,
+    ) -> eyre::Result<ExecutionResult> {
+        let mut evm = self.evm();
+        let mut env = evm.env.clone();
+        let apply = |env: &mut revm::primitives::Env| {
+            if let Some(number) = block.number {
+                env.block.number = number.into();
+            }
+            if let Some(timestamp) = block.timestamp {
+                env.block.timestamp = RevmU256::from(timestamp.as_u64());
+            }
+            if let Some(base_fee) = block.base_fee {
+                env.block.basefee = base_fee.into();
+            }
+        };
+        match number {
+            Some(number) => {
+                drop(evm);
+                self.at_block(number, |db, mined| {
+                    env.block = block_env(mined);
+                    apply(&mut env);
+                    self.transact_detached(env, OverrideDB::new(db, state)?, tx)
+                })
+            }
+            None => {
+                apply(&mut env);
+                let db = evm.db().expect("evm db");
+                self.transact_detached(env, OverrideDB::new(&*db, state)?, tx)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RevmClient;
+    use revm::primitives::TransactTo;
+
+    // returns slot 0, then the timestamp, the block number and the base fee
+    const ORACLE: &str = "6017600c60003960176000f360005460005242602052436040524860605260806000f3";
+
+    fn read(caller: Address, oracle: Address) -> TxEnv {
+        TxEnv {
+            caller: caller.into(),
+            transact_to: TransactTo::Call(oracle.into()),
+            ..Default::default()
+        }
+    }
+
+    fn words(output: &[u8]) -> Vec<U256> {
+        output.chunks(32).map(U256::from_big_endian).collect()
+    }
+
+    #[test]
+    fn overrides_state_and_block_for_one_call() {
+        let client = BasicClient::new();
+        let alice = client.create_account_with_balance(U256::from(10)).unwrap();
+        let deployed = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: ORACLE.parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        let pending = client.block_env();
+        let actual = words(&client.call(read(alice, deployed)).unwrap());
+        assert_eq!(actual[0], U256::zero());
+        assert_eq!(actual[1], pending.timestamp.into());
+        assert_eq!(actual[2], pending.number.into());
+
+        let mut slot = HashMap::new();
+        slot.insert(H256::zero(), H256::from_low_u64_be(42));
+        let mut state = StateOverride::new();
+        state.insert(
+            deployed,
+            AccountOverride {
+                state_diff: Some(slot.clone()),
+                ..Default::default()
+            },
+        );
+        let block = BlockOverrides {
+            number: Some(100.into()),
+            timestamp: Some(7.into()),
+            base_fee: Some(3.into()),
+        };
+        let output = client
+            .call_with_overrides(read(alice, deployed), &state, &block)
+            .unwrap();
+        assert_eq!(
+            words(&output),
+            vec![42.into(), 7.into(), 100.into(), 3.into()]
+        );
+        // nothing was changed for later calls
+        let after = words(&client.call(read(alice, deployed)).unwrap());
+        assert_eq!(after, actual);
+
+        // code and balance on an account that doesn't exist
+        let elsewhere = crate::generate_random_account();
+        let runtime = client.get_code(deployed);
+        let mut state = StateOverride::new();
+        state.insert(
+            elsewhere,
+            AccountOverride {
+                code: Some(runtime),
+                state: Some(slot),
+                ..Default::default()
+            },
+        );
+        state.insert(
+            alice,
+            AccountOverride {
+                balance: Some(U256::zero()),
+                ..Default::default()
+            },
+        );
+        let output = client
+            .call_with_overrides(read(alice, elsewhere), &state, &Default::default())
+            .unwrap();
+        assert_eq!(words(&output)[0], 42.into());
+        assert!(client.get_code(elsewhere).is_empty());
+        assert_eq!(client.get_balance(alice), U256::from(10));
+    }
+
+    #[test]
+    fn reads_geth_override_sets() {
+        let json = r#"{
+            "0x00000000000000000000000000000000000000aa": {
+                "balance": "0xde0b6b3a7640000",
+                "nonce": "0x2",
+                "stateDiff": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000000": "0x000000000000000000000000000000000000000000000000000000000000002a"
+                }
+            }
+        }"#;
+        let state: StateOverride = serde_json::from_str(json).unwrap();
+        let account = state.values().next().unwrap();
+        assert_eq!(account.balance, Some(U256::exp10(18)));
+        assert_eq!(account.nonce, Some(2.into()));
+        assert!(account.state.is_none());
+
+        let block: BlockOverrides =
+            serde_json::from_str(r#"{ "number": "0x10", "time": "0x20", "baseFee": "0x1" }"#)
+                .unwrap();
+        assert_eq!(block.timestamp, Some(0x20.into()));
+        assert_eq!(block.base_fee, Some(U256::one()));
+    }
+}
@@ -0,0 +1,716 @@
+//!
+//! An ethers `JsonRpcClient` backed by a [`BasicClient`]
+//!
+//! Wrapping a client in [`RevmupTransport`] lets the regular ethers `Provider` (and any
+//! ethers-generated contract bindings) talk to revm directly, without a socket:
+//!
+//! ```no_run
+//! use ethers_providers::Provider;
+//! use revmup_client::{BasicClient, RevmupTransport};
+//! use std::sync::Arc;
+//!
+//! let client = Arc::new(BasicClient::new());
+//! let provider = Provider::new(RevmupTransport::new(client.clone()));
+//! ```
+//!
+use crate::{
+    access_list::to_tx_access_list, builder::DEFAULT_TX_GAS_LIMIT, BasicClient, BlockOverrides,
+    StateOverride,
+};
+use async_trait::async_trait;
+use ethers_core::types::{
+    transaction::{eip2718::TypedTransaction, eip2930::AccessListWithGasUsed},
+    Address, Block, BlockNumber, Bytes, FeeHistory, Filter, NameOrAddress, H256, U256, U512, U64,
+};
+use ethers_providers::{JsonRpcClient, JsonRpcError, ProviderError, RpcError};
+use revm::primitives::{ExecutionResult, TransactTo, TxEnv};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+/// JSON-RPC error code for an unknown method
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for invalid method parameters
+const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC error code used by nodes for failed execution
+const SERVER_ERROR: i64 = -32000;
+/// JSON-RPC error code used by nodes for reverted `eth_call`/`eth_estimateGas`
+const EXECUTION_REVERTED: i64 = 3;
+
+/// Errors returned by [`RevmupTransport`]
+#[derive(Debug)]
+pub enum RevmupTransportError {
+    /// (De)Serialization error
+    SerdeJson(serde_json::Error),
+    /// Error response mirroring what a node would return
+    JsonRpcError(JsonRpcError),
+}
+
+impl fmt::Display for RevmupTransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SerdeJson(e) => write!(f, "{e}"),
+            Self::JsonRpcError(e) => write!(f, "JSON-RPC error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RevmupTransportError {}
+
+impl From<serde_json::Error> for RevmupTransportError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::SerdeJson(e)
+    }
+}
+
+impl From<RevmupTransportError> for ProviderError {
+    fn from(e: RevmupTransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+impl RpcError for RevmupTransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::JsonRpcError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::SerdeJson(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn rpc_error(code: i64, message: impl Into<String>, data: Option<Value>) -> RevmupTransportError {
+    RevmupTransportError::JsonRpcError(JsonRpcError {
+        code,
+        message: message.into(),
+        data,
+    })
+}
+
+/// An ethers transport that executes requests directly against a [`BasicClient`].
+///
+/// Transactions sent through the transport are mined immediately and recorded in the
+/// client's [`History`](crate::History).
+pub struct RevmupTransport {
+    client: Arc<BasicClient>,
+}
+
+impl Debug for RevmupTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RevmupTransport")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RevmupTransport {
+    /// Create a transport over the given client
+    pub fn new(client: Arc<BasicClient>) -> Self {
+        Self { client }
+    }
+
+    /// Returns the wrapped client
+    pub fn client(&self) -> Arc<BasicClient> {
+        self.client.clone()
+    }
+
+    /// Dispatch a single JSON-RPC request
+    fn handle(&self, method: &str, params: Value) -> Result<Value, RevmupTransportError> {
+        let params = match params {
+            Value::Array(params) => params,
+            Value::Null => Vec::new(),
+            other => vec![other],
+        };
+
+        let result = match method {
+            "eth_chainId" => to_value(U64::from(self.client.chain_id().as_u64()))?,
+            "net_version" => to_value(self.client.chain_id().to_string())?,
+            "eth_blockNumber" => to_value(U64::from(self.client.block_number().as_u64()))?,
+            "eth_accounts" => to_value(Vec::<Address>::new())?,
+            "eth_gasPrice" => {
+                to_value(U256::from(self.client.block_env().basefee) + self.client.priority_fee())?
+            }
+            "eth_maxPriorityFeePerGas" => to_value(self.client.priority_fee())?,
+            "eth_getBalance" => {
+                let account: Address = param(&params, 0)?;
+                match self.past_block(&params, 1) {
+                    Some(number) => to_value(
+                        self.client
+                            .get_balance_at(account, number)
+                            .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?,
+                    )?,
+                    None => to_value(self.client.get_balance(account))?,
+                }
+            }
+            "eth_getTransactionCount" => {
+                let account: Address = param(&params, 0)?;
+                let nonce = match param::<BlockNumber>(&params, 1) {
+                    Ok(BlockNumber::Pending) => self.client.get_pending_nonce(account),
+                    _ => match self.past_block(&params, 1) {
+                        Some(number) => self
+                            .client
+                            .get_nonce_at(account, number)
+                            .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?,
+                        None => self.client.get_nonce(account),
+                    },
+                };
+                to_value(U256::from(nonce))?
+            }
+            "eth_getCode" => {
+                let account: Address = param(&params, 0)?;
+                match self.past_block(&params, 1) {
+                    Some(number) => to_value(
+                        self.client
+                            .get_code_at(account, number)
+                            .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?,
+                    )?,
+                    None => to_value(self.client.get_code(account))?,
+                }
+            }
+            "eth_getStorageAt" => {
+                let account: Address = param(&params, 0)?;
+                let slot: U256 = param(&params, 1)?;
+                let stored = match self.past_block(&params, 2) {
+                    Some(number) => self
+                        .client
+                        .get_storage_at_block(account, slot, number)
+                        .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?,
+                    None => self.client.get_storage_at(account, slot),
+                };
+                let mut value = [0u8; 32];
+                stored.to_big_endian(&mut value);
+                to_value(H256::from(value))?
+            }
+            "eth_getBlockByNumber" => {
+                let number: BlockNumber = param(&params, 0)?;
+                let history = self.client.history();
+                to_value(history.block(history.resolve_block_number(number)))?
+            }
+            "eth_getBlockByHash" => {
+                let hash: H256 = param(&params, 0)?;
+                to_value(self.client.history().block_by_hash(hash))?
+            }
+            "eth_getLogs" => {
+                let filter: Filter = param(&params, 0)?;
+                to_value(self.client.get_logs(&filter))?
+            }
+            "eth_feeHistory" => {
+                let count: U256 = param(&params, 0)?;
+                let percentiles: Vec<f64> = param(&params, 2).unwrap_or_default();
+                to_value(self.fee_history(count.as_usize(), percentiles.len()))?
+            }
+            "eth_call" => {
+                let tx: TypedTransaction = param(&params, 0)?;
+                let number = self.past_block(&params, 1);
+                let state: Option<StateOverride> = optional_param(&params, 2)?;
+                let block: Option<BlockOverrides> = optional_param(&params, 3)?;
+                let result = if state.is_some() || block.is_some() {
+                    self.client
+                        .transact_with_overrides(
+                            into_tx_env(&tx),
+                            number,
+                            &state.unwrap_or_default(),
+                            &block.unwrap_or_default(),
+                        )
+                        .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?
+                } else {
+                    match number {
+                        Some(number) => self
+                            .client
+                            .transact_at(into_tx_env(&tx), number)
+                            .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?,
+                        None => self.execute(&tx)?,
+                    }
+                };
+                match result {
+                    ExecutionResult::Success { output, .. } => {
+                        to_value(Bytes::from(output.into_data()))?
+                    }
+                    other => return Err(failed_execution(other)),
+                }
+            }
+            "eth_createAccessList" => {
+                let tx: TypedTransaction = param(&params, 0)?;
+                let list = self
+                    .client
+                    .create_access_list(into_tx_env(&tx))
+                    .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?;
+                to_value(AccessListWithGasUsed::from(list))?
+            }
+            "eth_estimateGas" => {
+                let tx: TypedTransaction = param(&params, 0)?;
+                to_value(U256::from(self.estimate_gas(&tx)?))?
+            }
+            "eth_sendTransaction" => {
+                let tx: TypedTransaction = param(&params, 0)?;
+                to_value(self.send_transaction(tx)?)?
+            }
+            "eth_getTransactionByHash" => {
+                let hash: H256 = param(&params, 0)?;
+                to_value(self.client.get_transaction(hash))?
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: H256 = param(&params, 0)?;
+                to_value(self.client.get_receipt(hash))?
+            }
+            _ => {
+                return Err(rpc_error(
+                    METHOD_NOT_FOUND,
+                    format!("unsupported method: {method}"),
+                    None,
+                ))
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// The block tag parameter at `index`, when it names a block before the latest
+    fn past_block(&self, params: &[Value], index: usize) -> Option<u64> {
+        let tag = param::<BlockNumber>(params, index).ok()?;
+        let history = self.client.history();
+        let number = history.resolve_block_number(tag);
+        (number < history.latest_block_number()).then_some(number)
+    }
+
+    /// Run `tx` against the client without committing the state changes
+    fn execute(&self, tx: &TypedTransaction) -> Result<ExecutionResult, RevmupTransportError> {
+        self.execute_tx(into_tx_env(tx))
+    }
+
+    fn execute_tx(&self, tx: TxEnv) -> Result<ExecutionResult, RevmupTransportError> {
+        self.client
+            .transact_ref(tx)
+            .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))
+    }
+
+    /// The lowest gas limit `tx` succeeds with, found by binary search like geth and
+    /// anvil do. The search starts from the transaction's gas limit, or from the cap
+    /// on transactions without one.
+    fn estimate_gas(&self, tx: &TypedTransaction) -> Result<u64, RevmupTransportError> {
+        let tx = into_tx_env(tx);
+        let succeeds = |gas_limit: u64| {
+            let tx = TxEnv {
+                gas_limit,
+                ..tx.clone()
+            };
+            matches!(
+                self.client.transact_ref(tx),
+                Ok(ExecutionResult::Success { .. })
+            )
+        };
+
+        let mut high = tx.gas_limit;
+        if high == u64::MAX {
+            let block_gas_limit = self.client.block_env().gas_limit.saturating_to();
+            high = DEFAULT_TX_GAS_LIMIT.min(block_gas_limit);
+        }
+        let gas_used = match self.execute_tx(TxEnv {
+            gas_limit: high,
+            ..tx.clone()
+        })? {
+            ExecutionResult::Success { gas_used, .. } => gas_used,
+            other => return Err(failed_execution(other)),
+        };
+        // the gas used is net of refunds, so lower limits run out of gas
+        if succeeds(gas_used) {
+            return Ok(gas_used);
+        }
+        let mut low = gas_used;
+        while low + 1 < high {
+            let mid = low + (high - low) / 2;
+            if succeeds(mid) {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        Ok(high)
+    }
+
+    /// Mine `tx`, returning its hash. Like a node, reverted transactions are mined with a
+    /// failed receipt rather than returning an error.
+    ///
+    /// When the client doesn't automine, `tx` is added to its mempool instead.
+    fn send_transaction(&self, mut tx: TypedTransaction) -> Result<H256, RevmupTransportError> {
+        if !self.client.automine() {
+            return self
+                .client
+                .submit_transaction(into_tx_env(&tx))
+                .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None));
+        }
+        if tx.nonce().is_none() {
+            let from = tx.from().copied().unwrap_or_default();
+            tx.set_nonce(self.client.get_nonce(from));
+        }
+        let (hash, _) = self
+            .client
+            .mine_transaction(into_tx_env(&tx))
+            .map_err(|e| rpc_error(SERVER_ERROR, e.to_string(), None))?;
+        Ok(hash)
+    }
+
+    /// Fee history for the last `count` mined blocks. Rewards report the client's
+    /// priority fee.
+    fn fee_history(&self, count: usize, percentiles: usize) -> FeeHistory {
+        let history = self.client.history();
+        let latest = history.latest_block_number();
+        let oldest = (latest + 1).saturating_sub(count as u64);
+        let blocks = (oldest..=latest)
+            .filter_map(|number| history.block(number))
+            .collect::<Vec<_>>();
+
+        let mut base_fee_per_gas = blocks
+            .iter()
+            .map(|block| block.base_fee_per_gas.unwrap_or_default())
+            .collect::<Vec<_>>();
+        base_fee_per_gas.push(U256::from(self.client.block_env().basefee));
+        let gas_used_ratio = blocks.iter().map(|block| gas_used_ratio(block)).collect();
+        FeeHistory {
+            base_fee_per_gas,
+            gas_used_ratio,
+            oldest_block: oldest.into(),
+            reward: vec![vec![self.client.priority_fee(); percentiles]; blocks.len()],
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcClient for RevmupTransport {
+    type Error = RevmupTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        let result = self.handle(method, params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+/// Convert an ethers transaction into a revm `TxEnv`
+fn into_tx_env(tx: &TypedTransaction) -> TxEnv {
+    let mut env = TxEnv {
+        caller: tx.from().copied().unwrap_or_default().into(),
+        transact_to: match tx.to() {
+            Some(NameOrAddress::Address(to)) => TransactTo::Call((*to).into()),
+            _ => TransactTo::create(),
+        },
+        value: tx.value().copied().unwrap_or_default().into(),
+        data: tx.data().cloned().unwrap_or_default().0,
+        nonce: tx.nonce().map(|n| n.as_u64()),
+        ..Default::default()
+    };
+    if let Some(gas) = tx.gas() {
+        env.gas_limit = gas.as_u64();
+    }
+    match tx {
+        TypedTransaction::Eip1559(tx) => {
+            env.gas_price = tx.max_fee_per_gas.unwrap_or_default().into();
+            env.gas_priority_fee = tx.max_priority_fee_per_gas.map(Into::into);
+        }
+        _ => env.gas_price = tx.gas_price().unwrap_or_default().into(),
+    }
+    if let Some(access_list) = tx.access_list() {
+        env.access_list = to_tx_access_list(access_list);
+    }
+    env
+}
+
+/// The fraction of the block's gas limit that was used, to 6 decimal places
+fn gas_used_ratio(block: &Block<H256>) -> f64 {
+    const PRECISION: u64 = 1_000_000;
+    if block.gas_limit.is_zero() {
+        return 0.0;
+    }
+    let ratio = block.gas_used.full_mul(U256::from(PRECISION)) / U512::from(block.gas_limit);
+    ratio.low_u64() as f64 / PRECISION as f64
+}
+
+/// Build the error a node returns for a reverted or halted execution
+fn failed_execution(result: ExecutionResult) -> RevmupTransportError {
+    match result {
+        ExecutionResult::Revert { output, .. } => rpc_error(
+            EXECUTION_REVERTED,
+            "execution reverted",
+            Some(Value::String(Bytes::from(output).to_string())),
+        ),
+        ExecutionResult::Halt { reason, .. } => {
+            rpc_error(SERVER_ERROR, format!("execution halted: {reason:?}"), None)
+        }
+        ExecutionResult::Success { .. } => rpc_error(SERVER_ERROR, "unexpected success", None),
+    }
+}
+
+/// Deserialize the positional parameter at `index`
+fn param<P: DeserializeOwned>(params: &[Value], index: usize) -> Result<P, RevmupTransportError> {
+    let value = params.get(index).cloned().ok_or_else(|| {
+        rpc_error(
+            INVALID_PARAMS,
+            format!("missing parameter at index {index}"),
+            None,
+        )
+    })?;
+    serde_json::from_value(value)
+        .map_err(|e| rpc_error(INVALID_PARAMS, format!("invalid parameter: {e}"), None))
+}
+
+/// A trailing parameter that may be missing or null
+fn optional_param<P: DeserializeOwned>(
+    params: &[Value],
+    index: usize,
+) -> Result<Option<P>, RevmupTransportError> {
+    match params.get(index) {
+        None | Some(Value::Null) => Ok(None),
+        Some(_) => param(params, index).map(Some),
+    }
+}
+
+fn to_value<V: Serialize>(value: V) -> Result<Value, RevmupTransportError> {
+    Ok(serde_json::to_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_random_account, ClientBuilder};
+    use ethers_core::{types::TransactionRequest, utils::parse_ether};
+    use ethers_providers::{Middleware, Provider};
+    use std::time::Duration;
+
+    fn provider() -> (Arc<BasicClient>, Provider<RevmupTransport>) {
+        let client = Arc::new(BasicClient::new());
+        let provider =
+            Provider::new(RevmupTransport::new(client.clone())).interval(Duration::from_millis(1));
+        (client, provider)
+    }
+
+    #[tokio::test]
+    async fn reads_client_state() {
+        let (client, provider) = provider();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            provider.get_balance(alice, None).await.unwrap(),
+            parse_ether(1u8).unwrap()
+        );
+        assert_eq!(
+            provider.get_transaction_count(alice, None).await.unwrap(),
+            U256::zero()
+        );
+        assert_eq!(provider.get_chainid().await.unwrap(), client.chain_id());
+        assert_eq!(provider.get_block_number().await.unwrap(), U64::zero());
+    }
+
+    #[tokio::test]
+    async fn calls_with_state_overrides() {
+        let (_, provider) = provider();
+        let target = generate_random_account();
+        let tx: TypedTransaction = TransactionRequest::new().to(target).into();
+        // code that returns its slot 0
+        let overrides = serde_json::json!({
+            format!("{target:?}"): {
+                "code": "0x60005460005260206000f3",
+                "stateDiff": { format!("{:?}", H256::zero()): format!("{:?}", H256::from_low_u64_be(42)) }
+            }
+        });
+        let output: Bytes = provider
+            .request("eth_call", (tx, "latest", overrides))
+            .await
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&output), 42.into());
+        assert!(provider.get_code(target, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_transactions() {
+        let (client, provider) = provider();
+        let accounts = client
+            .batch_create_accounts_with_balance(2, parse_ether(1u8).unwrap())
+            .unwrap();
+        let (alice, bob) = (accounts[0], accounts[1]);
+
+        let tx = TransactionRequest::new().from(alice).to(bob).value(100u64);
+        let receipt = provider
+            .send_transaction(tx, None)
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .expect("receipt");
+
+        assert_eq!(receipt.status, Some(1u64.into()));
+        assert_eq!(receipt.gas_used, Some(21_000u64.into()));
+        assert_eq!(
+            client.get_balance(bob),
+            parse_ether(1u8).unwrap() + U256::from(100u64)
+        );
+        assert_eq!(client.get_nonce(alice), 1);
+
+        let tx = provider
+            .get_transaction(receipt.transaction_hash)
+            .await
+            .unwrap()
+            .expect("transaction");
+        assert_eq!(tx.from, alice);
+        assert_eq!(tx.to, Some(bob));
+    }
+
+    #[tokio::test]
+    async fn queues_transactions_without_automine() {
+        let client = Arc::new(ClientBuilder::new().automine(false).build());
+        let provider = Provider::new(RevmupTransport::new(client.clone()));
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let bob = generate_random_account();
+
+        let mut hashes = Vec::new();
+        for _ in 0..2 {
+            let nonce = provider
+                .get_transaction_count(alice, Some(BlockNumber::Pending.into()))
+                .await
+                .unwrap();
+            let tx = TransactionRequest::new()
+                .from(alice)
+                .to(bob)
+                .value(100u64)
+                .nonce(nonce);
+            hashes.push(*provider.send_transaction(tx, None).await.unwrap());
+        }
+        assert!(provider
+            .get_transaction_receipt(hashes[0])
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(client.pending_transactions().len(), 2);
+
+        let sealed = client.mine();
+        let mined = sealed
+            .receipts
+            .iter()
+            .map(|receipt| receipt.transaction_hash)
+            .collect::<Vec<_>>();
+        assert_eq!(mined, hashes);
+        assert_eq!(client.get_balance(bob), 200u64.into());
+    }
+
+    #[tokio::test]
+    async fn estimates_the_lowest_gas_limit() {
+        let (client, provider) = provider();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let bob = generate_random_account();
+        let transfer = TransactionRequest::new().from(alice).to(bob).value(1u64);
+        assert_eq!(
+            provider.estimate_gas(&transfer.into(), None).await.unwrap(),
+            21_000.into()
+        );
+
+        // clears slot 0, so part of the gas is refunded
+        let clearer = generate_random_account();
+        let storage = [(U256::zero(), U256::one())].into_iter().collect();
+        client
+            .etch(clearer, "0x6000600055".parse().unwrap(), storage)
+            .unwrap();
+        let clear: TypedTransaction = TransactionRequest::new().from(alice).to(clearer).into();
+        let estimate = provider.estimate_gas(&clear, None).await.unwrap().as_u64();
+        let call = |gas: u64| {
+            let mut tx = clear.clone();
+            tx.set_gas(gas);
+            let provider = &provider;
+            async move { provider.call(&tx, None).await }
+        };
+        assert!(call(estimate).await.is_ok());
+        assert!(call(estimate - 1).await.is_err());
+        let mut tx = clear.clone();
+        tx.set_gas(estimate);
+        let receipt = provider
+            .send_transaction(tx, None)
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .expect("receipt");
+        assert_eq!(receipt.status, Some(1u64.into()));
+        assert!(receipt.gas_used.unwrap() < estimate.into());
+    }
+
+    #[tokio::test]
+    async fn reads_nonces_and_code_at_past_blocks() {
+        let (client, provider) = provider();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        // init code that returns runtime code `STOP`
+        let init: Bytes = "0x60016000f3".parse().unwrap();
+        let tx = TransactionRequest::new().from(alice).data(init);
+        let receipt = provider
+            .send_transaction(tx, None)
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .expect("receipt");
+        let contract = receipt.contract_address.expect("contract address");
+
+        let at = |number: u64| Some(BlockNumber::Number(number.into()).into());
+        assert_eq!(
+            provider.get_transaction_count(alice, at(0)).await.unwrap(),
+            U256::zero()
+        );
+        assert_eq!(
+            provider.get_transaction_count(alice, None).await.unwrap(),
+            U256::one()
+        );
+        assert!(provider.get_code(contract, at(0)).await.unwrap().is_empty());
+        assert_eq!(
+            provider.get_code(contract, at(1)).await.unwrap(),
+            "0x00".parse::<Bytes>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_reverts() {
+        let (client, provider) = provider();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+
+        // init code that returns runtime code `PUSH1 0 PUSH1 0 REVERT`
+        let init: Bytes = "0x6005600c60003960056000f360006000fd".parse().unwrap();
+        let tx = TransactionRequest::new().from(alice).data(init);
+        let receipt = provider
+            .send_transaction(tx, None)
+            .await
+            .unwrap()
+            .await
+            .unwrap()
+            .expect("receipt");
+        let reverter = receipt.contract_address.expect("contract address");
+        assert_eq!(
+            provider.get_code(reverter, None).await.unwrap(),
+            "0x60006000fd".parse::<Bytes>().unwrap()
+        );
+
+        let call = TransactionRequest::new().from(alice).to(reverter).into();
+        let err = provider.call(&call, None).await.unwrap_err();
+        let rpc = err.as_error_response().expect("json rpc error");
+        assert_eq!(rpc.code, EXECUTION_REVERTED);
+    }
+}
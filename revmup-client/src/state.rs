@@ -0,0 +1,359 @@
+//!
+//! Snapshots of a client's accounts and block environment, in the JSON format of
+//! anvil's `--dump-state` and `--load-state`
+//!
+use ethers_core::types::{Address, Bytes, H256, U256};
+use revm::{
+    db::{AccountState, DatabaseRef},
+    primitives::{AccountInfo, BlockEnv, Bytecode, B160},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    advance_block, serve_block_hashes, store::AccountWithStorage, BasicClient, ClientBackend,
+    ClientDB,
+};
+
+/// The state of every account, and the block environment of the latest block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializableState {
+    #[serde(default)]
+    pub block: Option<SerializableBlockEnv>,
+    pub accounts: BTreeMap<Address, SerializableAccountRecord>,
+}
+
+/// An account's balance, nonce, code and storage
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializableAccountRecord {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// The block environment, with the field names of `revm::primitives::BlockEnv`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializableBlockEnv {
+    pub number: U256,
+    pub coinbase: Address,
+    pub timestamp: U256,
+    pub difficulty: U256,
+    #[serde(default)]
+    pub prevrandao: Option<H256>,
+    pub basefee: U256,
+    pub gas_limit: U256,
+}
+
+impl From<&BlockEnv> for SerializableBlockEnv {
+    fn from(env: &BlockEnv) -> Self {
+        Self {
+            number: env.number.into(),
+            coinbase: env.coinbase.into(),
+            timestamp: env.timestamp.into(),
+            difficulty: env.difficulty.into(),
+            prevrandao: env.prevrandao.map(Into::into),
+            basefee: env.basefee.into(),
+            gas_limit: env.gas_limit.into(),
+        }
+    }
+}
+
+impl From<&SerializableBlockEnv> for BlockEnv {
+    fn from(env: &SerializableBlockEnv) -> Self {
+        Self {
+            number: env.number.into(),
+            coinbase: env.coinbase.into(),
+            timestamp: env.timestamp.into(),
+            difficulty: env.difficulty.into(),
+            prevrandao: env.prevrandao.map(Into::into),
+            basefee: env.basefee.into(),
+            gas_limit: env.gas_limit.into(),
+        }
+    }
+}
+
+/// The environment of the latest block, from that of the block being built after it
+pub(crate) fn latest_block_env(pending: &BlockEnv) -> SerializableBlockEnv {
+    let mut block = SerializableBlockEnv::from(pending);
+    block.number = block.number.saturating_sub(U256::one());
+    block.timestamp = block.timestamp.saturating_sub(U256::one());
+    block
+}
+
+impl SerializableState {
+    /// Read a state from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Write the state to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> eyre::Result<()> {
+        Ok(fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+}
+
+impl BasicClient {
+    /// Snapshot every account and the block environment. The block is the latest
+    /// mined one, with the base fee of the block that follows it, as anvil dumps it.
+    ///
+    /// The accounts of a persisted client are read from its store too.
+    pub fn dump_state(&self) -> eyre::Result<SerializableState> {
+        let mut evm = self.evm();
+        let block = latest_block_env(&evm.env.block);
+        let db = evm.db().expect("evm db");
+
+        let mut merged = HashMap::new();
+        collect_accounts(db, &mut merged)?;
+        let mut accounts = BTreeMap::new();
+        for (address, (info, storage)) in merged {
+            let code = match info.code {
+                Some(code) => code,
+                None => DatabaseRef::code_by_hash(&*db, info.code_hash)?,
+            };
+            let storage = storage
+                .into_iter()
+                .filter(|(_, value)| !value.is_zero())
+                .map(|(slot, value)| (slot.into(), value.into()))
+                .collect();
+            let record = SerializableAccountRecord {
+                nonce: info.nonce,
+                balance: info.balance.into(),
+                code: code.original_bytes().into(),
+                storage,
+            };
+            accounts.insert(address.into(), record);
+        }
+
+        Ok(SerializableState {
+            block: Some(block),
+            accounts,
+        })
+    }
+
+    /// Load accounts and the block environment from a snapshot. Accounts in the
+    /// snapshot replace the client's, storage slots are merged.
+    ///
+    /// The snapshot's block can't be older than the latest block. A newer one is
+    /// recorded as an empty block, and the next block is mined after it.
+    pub fn load_state(&self, state: &SerializableState) -> eyre::Result<()> {
+        let mut evm = self.evm();
+        let mut history = self.history.write().expect("history lock poisoned");
+
+        let latest = history.latest_block_number();
+        if let Some(block) = &state.block {
+            if block.number < latest.into() {
+                eyre::bail!(
+                    "state is from block {}, before the latest block {}",
+                    block.number,
+                    latest
+                );
+            }
+        }
+
+        let db = evm.db().expect("evm db");
+        for (address, record) in &state.accounts {
+            history.record_write(
+                db,
+                (*address).into(),
+                record.storage.keys().map(|slot| (*slot).into()),
+            )?;
+            let info = AccountInfo {
+                balance: record.balance.into(),
+                nonce: record.nonce,
+                code: Some(Bytecode::new_raw(record.code.0.clone())),
+                ..Default::default()
+            };
+            db.insert_account_info((*address).into(), info);
+            for (slot, value) in &record.storage {
+                db.insert_account_storage((*address).into(), (*slot).into(), (*value).into())
+                    .expect("account was just inserted");
+            }
+        }
+
+        if let Some(block) = &state.block {
+            evm.env.block = block.into();
+            if block.number > latest.into() {
+                history.push_block(&evm.env.block, Vec::new(), Default::default());
+            }
+            advance_block(&mut evm.env.block);
+            serve_block_hashes(evm.db().expect("evm db"), &history);
+        }
+        Ok(())
+    }
+}
+
+/// Merge the accounts of `db`'s backend with those it caches on top, by address
+fn collect_accounts(
+    db: &ClientDB,
+    accounts: &mut HashMap<B160, AccountWithStorage>,
+) -> std::io::Result<()> {
+    match &db.db {
+        ClientBackend::Empty(_) => {}
+        ClientBackend::Disk(store) => accounts.extend(store.accounts()?),
+        // the state the client had when it was forked
+        ClientBackend::Fork(frozen) => collect_accounts(frozen, accounts)?,
+    }
+    for (address, account) in &db.accounts {
+        let mut storage = match account.account_state {
+            AccountState::NotExisting => {
+                accounts.remove(address);
+                continue;
+            }
+            AccountState::StorageCleared => HashMap::new(),
+            AccountState::Touched | AccountState::None => accounts
+                .remove(address)
+                .map(|(_, storage)| storage)
+                .unwrap_or_default(),
+        };
+        let mut info = account.info.clone();
+        if info.code.is_none() {
+            info.code = db.contracts.get(&info.code_hash).cloned();
+        }
+        storage.extend(account.storage.iter().map(|(slot, value)| (*slot, *value)));
+        accounts.insert(*address, (info, storage));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RevmClient;
+    use ethers_core::utils::parse_ether;
+    use revm::primitives::{TransactTo, TxEnv};
+
+    #[test]
+    fn dumps_and_loads_state() {
+        let client = BasicClient::builder().base_fee(7.into()).build();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        // stores 0x2a at slot 1 when deployed, has code `0x00`
+        let contract = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: "602a60015560016000f3".parse::<Bytes>().unwrap().0,
+                gas_limit: 100_000,
+                gas_price: revm::primitives::U256::from(14),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let state = client.dump_state().unwrap();
+        let json = serde_json::to_string(&state).unwrap();
+        let state: SerializableState = serde_json::from_str(&json).unwrap();
+        let block = state.block.clone().unwrap();
+        assert_eq!(block.number, 1.into());
+        assert_eq!(state.accounts[&contract].nonce, 1);
+        assert_eq!(state.accounts[&alice].nonce, 1);
+
+        let loaded = BasicClient::new();
+        loaded.load_state(&state).unwrap();
+        assert_eq!(loaded.get_balance(alice), client.get_balance(alice));
+        assert_eq!(loaded.get_nonce(alice), 1);
+        assert_eq!(loaded.get_code(contract), client.get_code(contract));
+        assert_eq!(loaded.get_storage_at(contract, 1.into()), 0x2a.into());
+        assert_eq!(loaded.block_number(), 1.into());
+        assert_eq!(loaded.block_env(), client.block_env());
+        assert_eq!(loaded.dump_state().unwrap(), client.dump_state().unwrap());
+
+        // the snapshot can't rewind a client
+        let older = SerializableState {
+            block: Some(SerializableBlockEnv::default()),
+            ..Default::default()
+        };
+        assert!(loaded.load_state(&older).is_err());
+        assert!(loaded.load_state(&state).is_ok());
+        loaded.open_block().seal();
+        assert!(loaded.load_state(&state).is_err());
+    }
+
+    #[test]
+    fn dumps_persisted_state() {
+        let path = tempfile::NamedTempFile::new().unwrap().into_temp_path();
+        let client = BasicClient::builder()
+            .persist(&path)
+            .unwrap()
+            .checkpoint_interval(2)
+            .build();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let contract = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: "602a60015560016000f3".parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        let bob = crate::generate_random_account();
+        // checkpoints the first two blocks, and drops them from memory
+        client.transfer(bob, alice, 1.into()).unwrap();
+
+        let state = client.dump_state().unwrap();
+        assert_eq!(state.accounts[&alice].nonce, 2);
+        assert_eq!(state.accounts[&bob].balance, 1.into());
+        let deployed = &state.accounts[&contract];
+        assert_eq!(deployed.code, "0x00".parse::<Bytes>().unwrap());
+        assert_eq!(deployed.storage[&1.into()], 0x2a.into());
+
+        // changes not yet checkpointed are dumped over the stored ones
+        client.transfer(bob, alice, 1.into()).unwrap();
+        let state = client.dump_state().unwrap();
+        assert_eq!(state.accounts[&bob].balance, 2.into());
+        assert_eq!(state.accounts[&contract].storage[&1.into()], 0x2a.into());
+    }
+
+    #[test]
+    fn reads_anvil_dumps() {
+        let json = r#"{
+            "block": {
+                "number": "0x5",
+                "coinbase": "0x0000000000000000000000000000000000000000",
+                "timestamp": "0x64",
+                "gas_limit": "0x1c9c380",
+                "basefee": "0x3b9aca00",
+                "difficulty": "0x0",
+                "prevrandao": "0x0000000000000000000000000000000000000000000000000000000000000000"
+            },
+            "accounts": {
+                "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266": {
+                    "nonce": 3,
+                    "balance": "0x21e19e0c9bab2400000",
+                    "code": "0x",
+                    "storage": {}
+                },
+                "0x5fbdb2315678afecb367f032d93f642f64180aa3": {
+                    "nonce": 1,
+                    "balance": "0x0",
+                    "code": "0x6080",
+                    "storage": { "0x0": "0x2a" }
+                }
+            }
+        }"#;
+        let state: SerializableState = serde_json::from_str(json).unwrap();
+        let client = BasicClient::new();
+        client.load_state(&state).unwrap();
+
+        let contract: Address = "0x5fbdb2315678afecb367f032d93f642f64180aa3"
+            .parse()
+            .unwrap();
+        assert_eq!(client.get_storage_at(contract, 0.into()), 0x2a.into());
+        assert_eq!(
+            client.get_code(contract),
+            "0x6080".parse::<Bytes>().unwrap()
+        );
+        assert_eq!(client.block_number(), 5.into());
+        assert_eq!(
+            client.block_env().timestamp,
+            revm::primitives::U256::from(101)
+        );
+    }
+}
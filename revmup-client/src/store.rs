@@ -0,0 +1,759 @@
+//!
+//! Persistent state on disk.
+//!
+//! [`DiskDB`] keeps accounts, storage and code in an append-only file, and only an
+//! index of where each value's latest record is in memory. Records are written in
+//! batches closed by a commit record. When the file is reopened, a batch without
+//! its commit record, e.g. after a crash, is discarded.
+//!
+use ethers_core::types::{Address, Bytes, H256, U256 as EthersU256};
+use revm::{
+    db::{AccountState, DatabaseRef, DbAccount, EmptyDB},
+    primitives::{
+        Account, AccountInfo, Bytecode, HashMap as RevmHashMap, B160, B256, KECCAK_EMPTY, U256,
+    },
+    Database, DatabaseCommit,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{state::SerializableBlockEnv, ClientDB};
+
+/// Where the client's state comes from when it isn't cached in memory
+#[derive(Debug, Clone)]
+pub enum ClientBackend {
+    /// Nothing, every account starts empty
+    Empty(EmptyDB),
+    /// A store on disk
+    Disk(Arc<DiskDB>),
+    /// The frozen state of a client at the time it was forked, shared with the fork
+    Fork(Arc<ClientDB>),
+}
+
+impl Default for ClientBackend {
+    fn default() -> Self {
+        Self::Empty(EmptyDB::default())
+    }
+}
+
+impl DatabaseRef for ClientBackend {
+    type Error = io::Error;
+
+    fn basic(&self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        match self {
+            Self::Empty(db) => db.basic(address).map_err(|e| match e {}),
+            Self::Disk(db) => db.basic(address),
+            Self::Fork(db) => db.basic(address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self {
+            Self::Empty(db) => db.code_by_hash(code_hash).map_err(|e| match e {}),
+            Self::Disk(db) => db.code_by_hash(code_hash),
+            Self::Fork(db) => db.code_by_hash(code_hash),
+        }
+    }
+
+    fn storage(&self, address: B160, index: U256) -> Result<U256, Self::Error> {
+        match self {
+            Self::Empty(db) => db.storage(address, index).map_err(|e| match e {}),
+            Self::Disk(db) => db.storage(address, index),
+            Self::Fork(db) => db.storage(address, index),
+        }
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        match self {
+            Self::Empty(db) => db.block_hash(number).map_err(|e| match e {}),
+            Self::Disk(db) => db.block_hash(number),
+            Self::Fork(db) => db.block_hash(number),
+        }
+    }
+}
+
+/// An account as it is stored, its code is stored separately by hash
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredAccount {
+    nonce: u64,
+    balance: EthersU256,
+    code_hash: H256,
+}
+
+/// One line of the store's file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    /// An account's info, `None` when it doesn't exist
+    Account {
+        address: Address,
+        account: Option<StoredAccount>,
+    },
+    Storage {
+        address: Address,
+        slot: EthersU256,
+        value: EthersU256,
+    },
+    /// Clears all of an account's storage
+    ClearStorage {
+        address: Address,
+    },
+    Code {
+        hash: H256,
+        code: Bytes,
+    },
+    /// Closes a batch of records, with the block the state is at when known
+    Commit {
+        block: Option<SerializableBlockEnv>,
+    },
+}
+
+/// An account's info and its storage slots
+pub(crate) type AccountWithStorage = (AccountInfo, HashMap<U256, U256>);
+
+/// Position and length of a record in the file
+type Location = (u64, u64);
+
+/// What the committed records say, by key
+#[derive(Debug, Default)]
+struct Index {
+    accounts: HashMap<B160, Location>,
+    storage: HashMap<B160, HashMap<U256, Location>>,
+    code: HashMap<B256, Location>,
+    block: Option<SerializableBlockEnv>,
+}
+
+impl Index {
+    fn apply(&mut self, record: &Record, location: Location) {
+        match record {
+            Record::Account { address, .. } => {
+                self.accounts.insert((*address).into(), location);
+            }
+            Record::Storage {
+                address,
+                slot,
+                value,
+            } => {
+                let slots = self.storage.entry((*address).into()).or_default();
+                if value.is_zero() {
+                    slots.remove(&(*slot).into());
+                } else {
+                    slots.insert((*slot).into(), location);
+                }
+            }
+            Record::ClearStorage { address } => {
+                self.storage.remove(&(*address).into());
+            }
+            Record::Code { hash, .. } => {
+                self.code.insert((*hash).into(), location);
+            }
+            Record::Commit { block } => {
+                if block.is_some() {
+                    self.block = block.clone();
+                }
+            }
+        }
+    }
+
+    /// Total length of the records still in use
+    fn live_len(&self) -> u64 {
+        let len = |locations: &mut dyn Iterator<Item = &Location>| -> u64 {
+            locations.map(|(_, len)| len).sum()
+        };
+        len(&mut self.accounts.values())
+            + len(&mut self.code.values())
+            + len(&mut self.storage.values().flat_map(HashMap::values))
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    file: File,
+    len: u64,
+    index: Index,
+}
+
+/// A `Database` stored in a file. See the [module docs](self).
+///
+/// Writes through `DatabaseCommit` panic if the file can't be written, as the trait
+/// can't return errors.
+#[derive(Debug)]
+pub struct DiskDB {
+    path: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+/// Compact when the file is this many times larger than its live records
+const COMPACTION_RATIO: u64 = 2;
+/// Don't bother compacting files smaller than this
+const COMPACTION_MIN_LEN: u64 = 1 << 20;
+
+impl DiskDB {
+    /// Open the store at `path`, creating it if it doesn't exist. A trailing batch
+    /// that was never committed, and a last record torn by a crash, are discarded.
+    /// Any other line that isn't a record is an error, and the file is left untouched.
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let inner = Self::load(&path)?;
+        Ok(Self {
+            path,
+            inner: Mutex::new(inner),
+        })
+    }
+
+    fn load(path: &Path) -> eyre::Result<Inner> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut index = Index::default();
+        let mut batch = Vec::new();
+        let mut committed = 0;
+        let mut offset = 0;
+        let mut reader = BufReader::new(&mut file);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let len = reader.read_line(&mut line)? as u64;
+            if len == 0 {
+                break;
+            }
+            // a torn write leaves a last line without its newline, after the records
+            // that made it to the file
+            if !line.ends_with('\n') && offset > 0 {
+                break;
+            }
+            let record = serde_json::from_str::<Record>(&line).map_err(|e| {
+                eyre::eyre!(
+                    "{} is not a store: bad record at byte {offset}: {e}",
+                    path.display()
+                )
+            })?;
+            let commit = matches!(record, Record::Commit { .. });
+            batch.push((record, (offset, len)));
+            offset += len;
+            if commit {
+                for (record, location) in batch.drain(..) {
+                    index.apply(&record, location);
+                }
+                committed = offset;
+            }
+        }
+
+        file.set_len(committed)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Inner {
+            file,
+            len: committed,
+            index,
+        })
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().expect("store lock poisoned")
+    }
+
+    /// The block of the last commit that recorded one
+    pub fn block(&self) -> Option<SerializableBlockEnv> {
+        self.inner().index.block.clone()
+    }
+
+    /// Size of the file in bytes
+    pub fn len(&self) -> u64 {
+        self.inner().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every account in the store, with its non-zero storage slots. Code is left
+    /// for `code_by_hash`.
+    pub(crate) fn accounts(&self) -> io::Result<Vec<(B160, AccountWithStorage)>> {
+        let addresses: Vec<B160> = self.inner().index.accounts.keys().copied().collect();
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let Some(info) = DatabaseRef::basic(self, address)? else {
+                continue;
+            };
+            let slots: Vec<U256> = self
+                .inner()
+                .index
+                .storage
+                .get(&address)
+                .map(|slots| slots.keys().copied().collect())
+                .unwrap_or_default();
+            let mut storage = HashMap::with_capacity(slots.len());
+            for slot in slots {
+                storage.insert(slot, DatabaseRef::storage(self, address, slot)?);
+            }
+            accounts.push((address, (info, storage)));
+        }
+        Ok(accounts)
+    }
+
+    fn read(inner: &mut Inner, (offset, len): Location) -> io::Result<Record> {
+        let mut buf = vec![0; len as usize];
+        inner.file.seek(SeekFrom::Start(offset))?;
+        inner.file.read_exact(&mut buf)?;
+        serde_json::from_slice(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Append `records` and a commit record closing them, and flush them to disk
+    fn append(&self, records: Vec<Record>, block: Option<SerializableBlockEnv>) -> io::Result<()> {
+        let mut inner = self.inner();
+        let inner = &mut *inner;
+
+        let mut buf = Vec::new();
+        let mut locations = Vec::with_capacity(records.len() + 1);
+        for record in records.into_iter().chain([Record::Commit { block }]) {
+            let start = buf.len() as u64;
+            serde_json::to_writer(&mut buf, &record)?;
+            buf.push(b'\n');
+            locations.push((record, (inner.len + start, buf.len() as u64 - start)));
+        }
+
+        inner.file.seek(SeekFrom::Start(inner.len))?;
+        if let Err(e) = inner
+            .file
+            .write_all(&buf)
+            .and_then(|_| inner.file.sync_data())
+        {
+            // drop the partial batch so it can't be committed by a later one
+            inner.file.set_len(inner.len)?;
+            return Err(e);
+        }
+        inner.len += buf.len() as u64;
+        for (record, location) in locations {
+            inner.index.apply(&record, location);
+        }
+        Ok(())
+    }
+
+    /// Write the accounts and code cached by `CacheDB` layers in one commit, at
+    /// `block`. Later layers override earlier ones.
+    pub(crate) fn write_cache(
+        &self,
+        layers: &[&ClientDB],
+        block: SerializableBlockEnv,
+    ) -> io::Result<()> {
+        let mut records = Vec::new();
+        for layer in layers {
+            self.push_layer(&mut records, &layer.accounts, &layer.contracts);
+        }
+        self.append(records, Some(block))
+    }
+
+    fn push_layer(
+        &self,
+        records: &mut Vec<Record>,
+        accounts: &RevmHashMap<B160, DbAccount>,
+        contracts: &RevmHashMap<B256, Bytecode>,
+    ) {
+        {
+            let inner = self.inner();
+            records.extend(
+                contracts
+                    .iter()
+                    .filter(|(hash, _)| {
+                        **hash != KECCAK_EMPTY
+                            && **hash != B256::zero()
+                            && !inner.index.code.contains_key(*hash)
+                    })
+                    .map(|(hash, code)| Record::Code {
+                        hash: (*hash).into(),
+                        code: code.original_bytes().into(),
+                    }),
+            );
+        }
+        for (address, account) in accounts {
+            let (info, cleared) = match account.account_state {
+                AccountState::NotExisting => (None, true),
+                AccountState::StorageCleared => (Some(&account.info), true),
+                AccountState::Touched | AccountState::None => (Some(&account.info), false),
+            };
+            // code is in `contracts`
+            push_account(records, *address, info, cleared);
+            for (slot, value) in &account.storage {
+                records.push(Record::Storage {
+                    address: (*address).into(),
+                    slot: (*slot).into(),
+                    value: (*value).into(),
+                });
+            }
+        }
+    }
+
+    /// Rewrite the file with only the records still in use, if enough of it is
+    /// overwritten values
+    pub fn compact_if_needed(&self) -> io::Result<()> {
+        let needed = {
+            let inner = self.inner();
+            inner.len > COMPACTION_MIN_LEN && inner.len > COMPACTION_RATIO * inner.index.live_len()
+        };
+        if needed {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the file with only the records still in use
+    pub fn compact(&self) -> io::Result<()> {
+        let mut inner = self.inner();
+        let inner = &mut *inner;
+
+        let mut locations = inner.index.code.values().copied().collect::<Vec<_>>();
+        locations.extend(inner.index.accounts.values());
+        locations.extend(inner.index.storage.values().flat_map(HashMap::values));
+        locations.sort();
+
+        let tmp = self.path.with_extension("compact");
+        let mut out = io::BufWriter::new(File::create(&tmp)?);
+        let mut buf = Vec::new();
+        for (offset, len) in locations {
+            buf.resize(len as usize, 0);
+            inner.file.seek(SeekFrom::Start(offset))?;
+            inner.file.read_exact(&mut buf)?;
+            out.write_all(&buf)?;
+        }
+        serde_json::to_writer(
+            &mut out,
+            &Record::Commit {
+                block: inner.index.block.clone(),
+            },
+        )?;
+        out.write_all(b"\n")?;
+        out.into_inner()?.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+
+        *inner = Self::load(&self.path).map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+fn push_account(
+    records: &mut Vec<Record>,
+    address: B160,
+    info: Option<&AccountInfo>,
+    cleared: bool,
+) {
+    if cleared {
+        records.push(Record::ClearStorage {
+            address: address.into(),
+        });
+    }
+    records.push(Record::Account {
+        address: address.into(),
+        account: info.map(|info| StoredAccount {
+            nonce: info.nonce,
+            balance: info.balance.into(),
+            code_hash: info.code_hash.into(),
+        }),
+    });
+}
+
+impl DatabaseRef for DiskDB {
+    type Error = io::Error;
+
+    fn basic(&self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        let mut inner = self.inner();
+        let Some(location) = inner.index.accounts.get(&address).copied() else {
+            return Ok(None);
+        };
+        match Self::read(&mut inner, location)? {
+            Record::Account { account, .. } => Ok(account.map(|account| AccountInfo {
+                nonce: account.nonce,
+                balance: account.balance.into(),
+                code_hash: account.code_hash.into(),
+                code: None,
+            })),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected an account",
+            )),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        let mut inner = self.inner();
+        let Some(location) = inner.index.code.get(&code_hash).copied() else {
+            return Ok(Bytecode::new());
+        };
+        match Self::read(&mut inner, location)? {
+            Record::Code { code, .. } => Ok(Bytecode::new_raw(code.0)),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "expected code")),
+        }
+    }
+
+    fn storage(&self, address: B160, index: U256) -> Result<U256, Self::Error> {
+        let mut inner = self.inner();
+        let Some(location) = inner
+            .index
+            .storage
+            .get(&address)
+            .and_then(|slots| slots.get(&index))
+            .copied()
+        else {
+            return Ok(U256::ZERO);
+        };
+        match Self::read(&mut inner, location)? {
+            Record::Storage { value, .. } => Ok(value.into()),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected a storage slot",
+            )),
+        }
+    }
+
+    fn block_hash(&self, number: U256) -> Result<B256, Self::Error> {
+        // the client serves the hashes of the blocks it mined
+        EmptyDB::default()
+            .block_hash(number)
+            .map_err(|e| match e {})
+    }
+}
+
+impl Database for DiskDB {
+    type Error = io::Error;
+
+    fn basic(&mut self, address: B160) -> Result<Option<AccountInfo>, Self::Error> {
+        DatabaseRef::basic(self, address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        DatabaseRef::code_by_hash(self, code_hash)
+    }
+
+    fn storage(&mut self, address: B160, index: U256) -> Result<U256, Self::Error> {
+        DatabaseRef::storage(self, address, index)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        DatabaseRef::block_hash(self, number)
+    }
+}
+
+impl DatabaseCommit for DiskDB {
+    fn commit(&mut self, changes: RevmHashMap<B160, Account>) {
+        let mut records = Vec::new();
+        for (address, account) in changes {
+            if account.is_destroyed {
+                push_account(&mut records, address, None, true);
+                continue;
+            }
+            if let Some(code) = account.info.code.as_ref().filter(|code| !code.is_empty()) {
+                records.push(Record::Code {
+                    hash: code.hash().into(),
+                    code: code.original_bytes().into(),
+                });
+            }
+            push_account(
+                &mut records,
+                address,
+                Some(&account.info),
+                account.storage_cleared,
+            );
+            for (slot, value) in account.storage {
+                records.push(Record::Storage {
+                    address: address.into(),
+                    slot: slot.into(),
+                    value: value.present_value().into(),
+                });
+            }
+        }
+        self.append(records, None)
+            .expect("failed to write to the state file");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::primitives::StorageSlot;
+
+    /// A store path, removed when dropped
+    fn temp_path() -> tempfile::TempPath {
+        tempfile::NamedTempFile::new().unwrap().into_temp_path()
+    }
+
+    #[test]
+    fn commits_and_reopens() {
+        let path = temp_path();
+        let alice = B160::from_low_u64_be(1);
+        let code = Bytecode::new_raw(vec![0x60, 0x00].into());
+
+        let mut db = DiskDB::open(&path).unwrap();
+        let mut account = Account::from(AccountInfo {
+            balance: U256::from(100),
+            nonce: 2,
+            code: Some(code.clone()),
+            code_hash: code.hash(),
+        });
+        account.storage.insert(
+            U256::from(1),
+            StorageSlot {
+                original_value: U256::ZERO,
+                present_value: U256::from(42),
+            },
+        );
+        db.commit([(alice, account)].into_iter().collect());
+
+        // a batch that was never committed
+        let len = db.len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"clear_storage\":{\"address\":\"0x0000000000000000000000000000000000000001\"}}\n{\"comm")
+            .unwrap();
+
+        let db = DiskDB::open(&path).unwrap();
+        assert_eq!(db.len(), len);
+        let info = DatabaseRef::basic(&db, alice).unwrap().unwrap();
+        assert_eq!((info.nonce, info.balance), (2, U256::from(100)));
+        assert_eq!(
+            db.code_by_hash(info.code_hash).unwrap().original_bytes(),
+            code.original_bytes()
+        );
+        assert_eq!(
+            DatabaseRef::storage(&db, alice, U256::from(1)).unwrap(),
+            U256::from(42)
+        );
+        assert_eq!(
+            DatabaseRef::basic(&db, B160::from_low_u64_be(2)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn refuses_files_that_are_not_stores() {
+        let path = temp_path();
+        let contents = "[settings]\nname = \"not a store\"\n";
+        fs::write(&path, contents).unwrap();
+        assert!(DiskDB::open(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+        // a single line without a newline isn't mistaken for a torn record
+        fs::write(&path, "{\"a\":1}").unwrap();
+        assert!(DiskDB::open(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\":1}");
+
+        // nor is a corrupted line between committed batches
+        let store = temp_path();
+        let mut db = DiskDB::open(&store).unwrap();
+        db.commit(
+            [(
+                B160::from_low_u64_be(1),
+                Account::from(AccountInfo::default()),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        let batch = fs::read_to_string(&store).unwrap();
+        let contents = format!("{batch}garbage\n{batch}");
+        fs::write(&path, &contents).unwrap();
+        assert!(DiskDB::open(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+    }
+
+    #[test]
+    fn resumes_client_from_checkpoint() {
+        use crate::{BasicClient, RevmClient};
+        use ethers_core::utils::parse_ether;
+        use revm::primitives::{TransactTo, TxEnv};
+
+        let path = temp_path();
+        let persisted = || BasicClient::builder().persist(&path).unwrap();
+        let client = persisted().checkpoint_interval(2).build();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        // stores 0x2a at slot 1 when deployed
+        let contract = client
+            .deploy(TxEnv {
+                caller: alice.into(),
+                transact_to: TransactTo::create(),
+                data: "602a60015560016000f3".parse::<Bytes>().unwrap().0,
+                ..Default::default()
+            })
+            .unwrap();
+        let bob = crate::generate_random_account();
+        client.transfer(bob, alice, 1.into()).unwrap();
+        assert_eq!(client.block_number(), 2.into());
+        // not checkpointed yet
+        client.transfer(bob, alice, 1.into()).unwrap();
+        drop(client);
+
+        let client = persisted().build();
+        assert_eq!(client.block_number(), 2.into());
+        assert_eq!(client.get_balance(bob), 1.into());
+        assert_eq!(client.get_nonce(alice), 2);
+        assert_eq!(client.get_storage_at(contract, 1.into()), 0x2a.into());
+        assert_eq!(client.get_code(contract), "0x00".parse::<Bytes>().unwrap());
+
+        client.transfer(bob, alice, 1.into()).unwrap();
+        let client = persisted().build();
+        assert_eq!(client.block_number(), 3.into());
+        assert_eq!(client.get_balance(bob), 2.into());
+    }
+
+    #[test]
+    fn resumes_client_without_reapplying_genesis() {
+        use crate::BasicClient;
+        use ethers_core::utils::{Genesis, GenesisAccount};
+
+        let path = temp_path();
+        let alice = crate::generate_random_account();
+        let bob = crate::generate_random_account();
+        let mut genesis = Genesis::default();
+        genesis.alloc.insert(
+            alice,
+            GenesisAccount {
+                balance: 10.into(),
+                ..Default::default()
+            },
+        );
+        let persisted = || {
+            BasicClient::builder()
+                .genesis(genesis.clone())
+                .persist(&path)
+                .unwrap()
+                .build()
+        };
+
+        let client = persisted();
+        client.transfer(bob, alice, 4.into()).unwrap();
+        drop(client);
+
+        let client = persisted();
+        assert_eq!(client.block_number(), 1.into());
+        assert_eq!(client.get_balance(alice), 6.into());
+        assert_eq!(client.get_balance(bob), 4.into());
+    }
+
+    #[test]
+    fn compacts_overwritten_records() {
+        let path = temp_path();
+        let alice = B160::from_low_u64_be(1);
+        let mut db = DiskDB::open(&path).unwrap();
+        for balance in 0..10 {
+            let account = Account::from(AccountInfo {
+                balance: U256::from(balance),
+                ..Default::default()
+            });
+            db.commit([(alice, account)].into_iter().collect());
+        }
+
+        let len = db.len();
+        db.compact().unwrap();
+        assert!(db.len() < len / 5);
+        let info = DatabaseRef::basic(&db, alice).unwrap().unwrap();
+        assert_eq!(info.balance, U256::from(9));
+    }
+}
@@ -0,0 +1,54 @@
+//!
+//! Live log subscriptions, notified as transactions are committed
+//!
+use ethers_core::types::{Filter, Log};
+use std::sync::Arc;
+
+use crate::history::matches_log;
+
+/// Callback invoked with each log matching a subscription
+pub type LogCallback = Box<dyn Fn(&Log) + Send + Sync>;
+
+/// Identifies a log subscription so it can be removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// The log subscriptions registered with a client
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    next_id: u64,
+    entries: Vec<(SubscriptionId, Filter, Arc<LogCallback>)>,
+}
+
+impl Subscriptions {
+    /// Register `callback` for logs matching `filter`
+    pub(crate) fn insert(&mut self, filter: Filter, callback: LogCallback) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.entries.push((id, filter, Arc::new(callback)));
+        id
+    }
+
+    /// Remove a subscription. Returns false if it was not registered
+    pub(crate) fn remove(&mut self, id: SubscriptionId) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|(entry, _, _)| *entry != id);
+        self.entries.len() != len
+    }
+
+    /// Pair each of `logs` with the callbacks of the subscriptions it matches, in
+    /// log order. The filters' block ranges are ignored.
+    ///
+    /// The callbacks are returned rather than invoked so the caller can release
+    /// its locks first, allowing callbacks to use the client.
+    pub(crate) fn matching(&self, logs: &[Log]) -> Vec<(Arc<LogCallback>, Log)> {
+        logs.iter()
+            .flat_map(|log| {
+                self.entries
+                    .iter()
+                    .filter(|(_, filter, _)| matches_log(filter, log))
+                    .map(|(_, _, callback)| (callback.clone(), log.clone()))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,260 @@
+//!
+//! Built-in inspector tracing the calls of each execution
+//!
+use ethers_core::types::{Address, Bytes, U256};
+use revm::{
+    interpreter::{CallInputs, CallScheme, CreateInputs, CreateScheme, Gas, InstructionResult},
+    primitives::{Bytes as RevmBytes, B160},
+    EVMData, Inspector,
+};
+
+use crate::ClientDB;
+
+/// How a call was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    CallCode,
+    DelegateCall,
+    StaticCall,
+    Create,
+    Create2,
+}
+
+/// A call made during an execution, with the calls it made in turn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallTrace {
+    pub kind: CallKind,
+    pub from: Address,
+    /// The called address, or the created one. Zero when a create failed
+    pub to: Address,
+    pub value: U256,
+    /// The call data, or the init code of a create
+    pub input: Bytes,
+    pub output: Bytes,
+    pub gas_used: u64,
+    /// False when the call reverted or halted
+    pub success: bool,
+    pub calls: Vec<CallTrace>,
+}
+
+/// Records the tree of calls of each execution it inspects, like geth's `callTracer`.
+/// Register it with `BasicClient::add_inspector` or run it with `BasicClient::inspect`.
+///
+/// ```
+/// # use revmup_client::{BasicClient, CallTracer};
+/// let client = BasicClient::new();
+/// let (_, tracer) = client.inspect(CallTracer::default(), || {
+///     // calls and transactions...
+/// });
+/// for trace in tracer.traces() {
+///     println!("{:?} {:?} -> {:?}", trace.kind, trace.from, trace.to);
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    traces: Vec<CallTrace>,
+    /// The calls in progress, outermost first
+    stack: Vec<CallTrace>,
+}
+
+impl CallTracer {
+    /// The trace of each execution, in the order they ran
+    pub fn traces(&self) -> &[CallTrace] {
+        &self.traces
+    }
+
+    pub fn into_traces(self) -> Vec<CallTrace> {
+        self.traces
+    }
+
+    fn enter(&mut self, kind: CallKind, from: B160, to: B160, value: U256, input: &RevmBytes) {
+        self.stack.push(CallTrace {
+            kind,
+            from: from.into(),
+            to: to.into(),
+            value,
+            input: input.clone().into(),
+            output: Bytes::default(),
+            gas_used: 0,
+            success: false,
+            calls: Vec::new(),
+        });
+    }
+
+    fn exit(
+        &mut self,
+        ret: InstructionResult,
+        gas_used: u64,
+        output: &RevmBytes,
+    ) -> Option<&mut CallTrace> {
+        let mut trace = self.stack.pop()?;
+        trace.gas_used = gas_used;
+        trace.output = output.clone().into();
+        trace.success = matches!(
+            ret,
+            InstructionResult::Continue
+                | InstructionResult::Stop
+                | InstructionResult::Return
+                | InstructionResult::SelfDestruct
+        );
+        let calls = match self.stack.last_mut() {
+            Some(parent) => &mut parent.calls,
+            None => &mut self.traces,
+        };
+        calls.push(trace);
+        calls.last_mut()
+    }
+}
+
+impl Inspector<ClientDB> for CallTracer {
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, ClientDB>,
+        inputs: &mut CallInputs,
+        _is_static: bool,
+    ) -> (InstructionResult, Gas, RevmBytes) {
+        let kind = match inputs.context.scheme {
+            CallScheme::Call => CallKind::Call,
+            CallScheme::CallCode => CallKind::CallCode,
+            CallScheme::DelegateCall => CallKind::DelegateCall,
+            CallScheme::StaticCall => CallKind::StaticCall,
+        };
+        self.enter(
+            kind,
+            inputs.context.caller,
+            inputs.contract,
+            inputs.transfer.value.into(),
+            &inputs.input,
+        );
+        (InstructionResult::Continue, Gas::new(0), RevmBytes::new())
+    }
+
+    fn call_end(
+        &mut self,
+        _data: &mut EVMData<'_, ClientDB>,
+        inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: RevmBytes,
+        _is_static: bool,
+    ) -> (InstructionResult, Gas, RevmBytes) {
+        let gas_used = inputs.gas_limit.saturating_sub(remaining_gas.remaining());
+        self.exit(ret, gas_used, &out);
+        (ret, remaining_gas, out)
+    }
+
+    fn create(
+        &mut self,
+        _data: &mut EVMData<'_, ClientDB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<B160>, Gas, RevmBytes) {
+        let kind = match inputs.scheme {
+            CreateScheme::Create => CallKind::Create,
+            CreateScheme::Create2 { .. } => CallKind::Create2,
+        };
+        self.enter(
+            kind,
+            inputs.caller,
+            B160::zero(),
+            inputs.value.into(),
+            &inputs.init_code,
+        );
+        (
+            InstructionResult::Continue,
+            None,
+            Gas::new(0),
+            RevmBytes::default(),
+        )
+    }
+
+    fn create_end(
+        &mut self,
+        _data: &mut EVMData<'_, ClientDB>,
+        inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<B160>,
+        remaining_gas: Gas,
+        out: RevmBytes,
+    ) -> (InstructionResult, Option<B160>, Gas, RevmBytes) {
+        let gas_used = inputs.gas_limit.saturating_sub(remaining_gas.remaining());
+        if let Some(trace) = self.exit(ret, gas_used, &out) {
+            trace.to = address.unwrap_or_default().into();
+        }
+        (ret, address, remaining_gas, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{generate_random_account, BasicClient, RevmClient};
+    use ethers_core::utils::parse_ether;
+    use revm::primitives::{TransactTo, TxEnv};
+    use std::collections::HashMap;
+
+    #[test]
+    fn traces_nested_calls() {
+        let client = BasicClient::new();
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        // reverts
+        let callee = generate_random_account();
+        client
+            .etch(callee, "0x60006000fd".parse().unwrap(), HashMap::new())
+            .unwrap();
+        // calls the callee with no data, and stops
+        let caller = generate_random_account();
+        let code = format!("0x6000600060006000600073{callee:x}5af100");
+        client
+            .etch(caller, code.parse().unwrap(), HashMap::new())
+            .unwrap();
+
+        let ((), tracer) = client.inspect(CallTracer::default(), || {
+            // init code returning the single byte runtime code `STOP`
+            let deployed = client
+                .deploy(TxEnv {
+                    caller: alice.into(),
+                    transact_to: TransactTo::create(),
+                    data: vec![0x60, 0x01, 0x60, 0x00, 0xf3].into(),
+                    ..Default::default()
+                })
+                .unwrap();
+            client
+                .send_transaction(TxEnv {
+                    caller: alice.into(),
+                    transact_to: TransactTo::Call(caller.into()),
+                    value: revm::primitives::U256::from(3),
+                    ..Default::default()
+                })
+                .unwrap();
+            assert_ne!(deployed, Address::zero());
+        });
+
+        let traces = tracer.into_traces();
+        assert_eq!(traces.len(), 2);
+        let create = &traces[0];
+        assert_eq!(create.kind, CallKind::Create);
+        assert_eq!(create.from, alice);
+        assert_ne!(create.to, Address::zero());
+        assert!(create.success);
+
+        let call = &traces[1];
+        assert_eq!(
+            (call.kind, call.from, call.to),
+            (CallKind::Call, alice, caller)
+        );
+        assert_eq!(call.value, 3.into());
+        assert!(call.success);
+        assert_eq!(call.calls.len(), 1);
+        let inner = &call.calls[0];
+        assert_eq!(
+            (inner.kind, inner.from, inner.to),
+            (CallKind::Call, caller, callee)
+        );
+        assert!(!inner.success);
+        assert!(inner.gas_used > 0 && inner.gas_used < call.gas_used);
+        assert!(inner.calls.is_empty());
+    }
+}
@@ -1,12 +1,14 @@
 use ethers_contract::decode_function_data;
 use ethers_core::{
     abi::{Detokenize, Function, RawLog},
-    types::Address,
+    types::{transaction::eip2930::AccessList, Address, H256, U256},
 };
 use revm::primitives::TxEnv;
 use std::{borrow::Borrow, fmt::Debug, marker::PhantomData};
 
-use revmup_client::RevmClient;
+use revmup_client::{
+    access_list::to_tx_access_list, AccessListWithGas, BlockOverrides, RevmClient, StateOverride,
+};
 
 pub type ContractCall<R, D> = FunctionCall<std::sync::Arc<R>, R, D>;
 
@@ -18,11 +20,66 @@ pub struct FunctionCall<B, R, D> {
     pub tx: TxEnv,
     /// The ABI of the function being called
     pub function: Function,
+    /// The sender set by `from`, which takes precedence over a `caller` argument
+    pub(crate) from: Option<Address>,
     pub(crate) client: B,
     pub(crate) datatype: PhantomData<D>,
     pub(crate) _m: PhantomData<R>,
 }
 
+/// Settings applied to the transaction by `call`, `send_transaction` and `submit`
+impl<B, R, D> FunctionCall<B, R, D> {
+    /// Sets the `from` field of the transaction. It takes precedence over the
+    /// `caller` given to `send_transaction`, `submit` and `create_access_list`.
+    pub fn from<T: Into<Address>>(mut self, from: T) -> Self {
+        let from = from.into();
+        self.tx.caller = from.into();
+        self.from = Some(from);
+        self
+    }
+
+    /// Sets the value of ether sent with the transaction
+    pub fn value<T: Into<U256>>(mut self, value: T) -> Self {
+        self.tx.value = value.into().into();
+        self
+    }
+
+    /// Sets the gas limit of the transaction
+    pub fn gas<T: Into<U256>>(mut self, gas: T) -> Self {
+        self.tx.gas_limit = saturating_u64(gas.into());
+        self
+    }
+
+    /// Sets the gas price of the transaction, or its max fee per gas when a max
+    /// priority fee is set
+    pub fn gas_price<T: Into<U256>>(mut self, gas_price: T) -> Self {
+        self.tx.gas_price = gas_price.into().into();
+        self
+    }
+
+    /// Sets the max priority fee per gas of the transaction
+    pub fn max_priority_fee<T: Into<U256>>(mut self, max_priority_fee: T) -> Self {
+        self.tx.gas_priority_fee = Some(max_priority_fee.into().into());
+        self
+    }
+
+    /// Sets the nonce of the transaction
+    pub fn nonce<T: Into<U256>>(mut self, nonce: T) -> Self {
+        self.tx.nonce = Some(saturating_u64(nonce.into()));
+        self
+    }
+
+    /// Sets the EIP-2930 access list of the transaction
+    pub fn access_list(mut self, access_list: AccessList) -> Self {
+        self.tx.access_list = to_tx_access_list(&access_list);
+        self
+    }
+}
+
+pub(crate) fn saturating_u64(value: U256) -> u64 {
+    value.try_into().unwrap_or(u64::MAX)
+}
+
 impl<B, R, D> FunctionCall<B, R, D>
 where
     B: Borrow<R>,
@@ -35,14 +92,59 @@ where
         Ok(data)
     }
 
+    /// Call the function against the state as it was after block `number`
+    pub fn call_at(&self, number: u64) -> eyre::Result<D> {
+        let bits = self.client.borrow().call_at(self.tx.clone(), number)?;
+        let data = decode_function_data(&self.function, &bits, false)?;
+        Ok(data)
+    }
+
+    /// Call the function seeing the accounts in `state` and the block values in
+    /// `block` as overridden, for this call only
+    pub fn call_with_overrides(
+        &self,
+        state: &StateOverride,
+        block: &BlockOverrides,
+    ) -> eyre::Result<D> {
+        let bits = self
+            .client
+            .borrow()
+            .call_with_overrides(self.tx.clone(), state, block)?;
+        let data = decode_function_data(&self.function, &bits, false)?;
+        Ok(data)
+    }
+
+    /// Send the transaction from `caller`, unless `from` set the sender
     pub fn send_transaction(&self, caller: Address) -> eyre::Result<(D, Vec<RawLog>)> {
-        let mut t = self.tx.to_owned();
-        t.caller = caller.into();
+        let t = self.tx_from(caller);
         let (bits, _, logs) = self.client.borrow().send_transaction(t)?;
         let data = decode_function_data(&self.function, &bits, false)?;
         //let rl =
         Ok((data, logs))
     }
+
+    /// Generate the access list of the transaction sent by `caller`, unless `from`
+    /// set the sender, and the gas it uses with and without it
+    pub fn create_access_list(&self, caller: Address) -> eyre::Result<AccessListWithGas> {
+        self.client
+            .borrow()
+            .create_access_list(self.tx_from(caller))
+    }
+
+    /// Add the transaction from `caller`, unless `from` set the sender, to the
+    /// client's mempool instead of executing it, returning its hash
+    pub fn submit(&self, caller: Address) -> eyre::Result<H256> {
+        self.client
+            .borrow()
+            .submit_transaction(self.tx_from(caller))
+    }
+
+    /// The transaction, sent by `caller` unless `from` set the sender
+    fn tx_from(&self, caller: Address) -> TxEnv {
+        let mut t = self.tx.to_owned();
+        t.caller = self.from.unwrap_or(caller).into();
+        t
+    }
 }
 
 impl<B, R, D> Clone for FunctionCall<B, R, D>
@@ -53,9 +155,106 @@ where
         FunctionCall {
             tx: self.tx.clone(),
             function: self.function.clone(),
+            from: self.from,
             client: self.client.clone(),
             datatype: self.datatype,
             _m: self._m,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::Contract;
+    use ethers_core::{
+        abi::parse_abi, types::transaction::eip2930::AccessListItem, utils::parse_ether,
+    };
+    use revmup_client::BasicClient;
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::*;
+
+    // returns the caller
+    const WHOAMI: &str = "0x3360005260206000f3";
+
+    fn setup() -> (Arc<BasicClient>, Contract<BasicClient>) {
+        let client = Arc::new(BasicClient::builder().base_fee(7.into()).build());
+        let target = revmup_client::generate_random_account();
+        client
+            .etch(target, WHOAMI.parse().unwrap(), HashMap::new())
+            .unwrap();
+        let abi = parse_abi(&["function poke() payable returns (address)"]).unwrap();
+        let contract = Contract::new(target, abi, client.clone());
+        (client, contract)
+    }
+
+    fn funded(client: &BasicClient) -> Address {
+        client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap()
+    }
+
+    fn poke(contract: &Contract<BasicClient>) -> ContractCall<BasicClient, Address> {
+        contract.method("poke", ()).unwrap()
+    }
+
+    #[test]
+    fn sends_the_transaction_fields() {
+        let (client, contract) = setup();
+        let alice = funded(&client);
+        let access_list = AccessList(vec![AccessListItem {
+            address: contract.address(),
+            storage_keys: vec![H256::zero()],
+        }]);
+
+        let (sender, _) = poke(&contract)
+            .value(5)
+            .gas(100_000)
+            .gas_price(20)
+            .max_priority_fee(3)
+            .nonce(0)
+            .access_list(access_list)
+            .send_transaction(alice)
+            .unwrap();
+        assert_eq!(sender, alice);
+        let hash = client.get_block(1).unwrap().transactions[0];
+        let tx = client.get_transaction(hash).unwrap();
+        assert_eq!(tx.from, alice);
+        assert_eq!(tx.value, 5.into());
+        assert_eq!(tx.gas, 100_000.into());
+        assert_eq!(tx.max_fee_per_gas, Some(20.into()));
+        assert_eq!(tx.max_priority_fee_per_gas, Some(3.into()));
+        assert_eq!(tx.nonce, 0.into());
+        assert_eq!(client.get_balance(contract.address()), 5.into());
+
+        // the same call without the list: an address costs 2400 gas, a key 1900
+        poke(&contract).send_transaction(alice).unwrap();
+        let gas_used = |number| {
+            let hash = client.get_block(number).unwrap().transactions[0];
+            client.get_receipt(hash).unwrap().gas_used.unwrap()
+        };
+        assert_eq!(gas_used(1) - gas_used(2), 4_300.into());
+
+        // a nonce other than the sender's is rejected
+        assert!(poke(&contract).nonce(0).send_transaction(alice).is_err());
+    }
+
+    #[test]
+    fn from_takes_precedence_over_the_caller() {
+        let (client, contract) = setup();
+        let (alice, bob) = (funded(&client), funded(&client));
+
+        assert_eq!(poke(&contract).from(bob).call().unwrap(), bob);
+        let (sender, _) = poke(&contract).from(bob).send_transaction(alice).unwrap();
+        assert_eq!(sender, bob);
+        assert_eq!(client.get_nonce(bob), 1);
+        assert_eq!(client.get_nonce(alice), 0);
+
+        poke(&contract).from(bob).submit(alice).unwrap();
+        assert_eq!(client.pending_transactions()[0].sender(), bob.into());
+
+        // without `from`, the caller sends it
+        let (sender, _) = poke(&contract).send_transaction(alice).unwrap();
+        assert_eq!(sender, alice);
+    }
+}
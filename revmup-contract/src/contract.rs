@@ -1,12 +1,12 @@
-use ethers_contract::{encode_function_data, AbiError, BaseContract};
+use ethers_contract::{encode_function_data, AbiError, BaseContract, EthEvent, EthLogDecode};
 use ethers_core::{
     abi::{Abi, Detokenize, Error, Function, Tokenize},
-    types::{Address, Selector},
+    types::{Address, Filter, Selector},
 };
 use revm::primitives::{TransactTo, TxEnv};
 use std::{borrow::Borrow, fmt::Debug, marker::PhantomData};
 
-use crate::call::FunctionCall;
+use crate::{call::FunctionCall, event::Event};
 use revmup_client::RevmClient;
 
 pub type Contract<R> = ContractInstance<::std::sync::Arc<R>, R>;
@@ -55,7 +55,7 @@ where
 
     /// Returns a reference to the contract's ABI.
     pub fn abi(&self) -> &Abi {
-        &self.base_contract.abi()
+        self.base_contract.abi()
     }
 }
 
@@ -91,14 +91,17 @@ where
     ) -> Result<FunctionCall<B, R, D>, AbiError> {
         let data = encode_function_data(function, args)?;
 
-        let mut tx = TxEnv::default();
-        tx.transact_to = TransactTo::Call(self.address.into());
-        tx.data = data.to_vec().into();
+        let tx = TxEnv {
+            transact_to: TransactTo::Call(self.address.into()),
+            data: data.to_vec().into(),
+            ..Default::default()
+        };
 
         Ok(FunctionCall {
             tx,
             client: self.client.clone(),
             function: function.to_owned(),
+            from: None,
             datatype: PhantomData,
             _m: self._m,
         })
@@ -133,6 +136,23 @@ where
         self.method_func(function, args)
     }
 
+    /// Returns an [`Event`] builder for the provided event, filtered to this contract
+    pub fn event<D: EthEvent>(&self) -> Event<B, R, D> {
+        self.event_with_filter(Filter::new().event(&D::abi_signature()))
+    }
+
+    /// Returns an [`Event`] builder with the provided filter, restricted to this
+    /// contract's address. Logs are decoded into `D`, typically the contract's
+    /// events enum
+    pub fn event_with_filter<D: EthLogDecode>(&self, filter: Filter) -> Event<B, R, D> {
+        Event {
+            filter: filter.address(self.address),
+            client: self.client.clone(),
+            datatype: PhantomData,
+            _m: self._m,
+        }
+    }
+
     pub fn client(&self) -> B {
         self.client.clone()
     }
@@ -0,0 +1,164 @@
+use ethers_core::types::{Address, TransactionReceipt, H256, U256};
+use revm::primitives::{CreateScheme, TransactTo, TxEnv};
+use std::{borrow::Borrow, marker::PhantomData};
+
+use crate::call::saturating_u64;
+use revmup_client::{create2_address, RevmClient};
+
+pub type ContractDeployment<R, C> = ContractDeployer<std::sync::Arc<R>, R, C>;
+
+#[derive(Debug)]
+#[must_use = "deployments do nothing unless you `send` them"]
+/// Helper for deploying a contract, returning a typed instance of it
+pub struct ContractDeployer<B, R, C> {
+    /// The raw deployment transaction, with the contract's init code as data
+    pub tx: TxEnv,
+    salt: Option<H256>,
+    factory: Option<Address>,
+    client: B,
+    instance: fn(Address, B) -> C,
+    _m: PhantomData<R>,
+}
+
+impl<B, R, C> ContractDeployer<B, R, C> {
+    /// Creates a deployment of `init_code`, the contract's bytecode followed by
+    /// its encoded constructor arguments. `instance` binds the deployed contract.
+    pub fn new(client: B, init_code: Vec<u8>, instance: fn(Address, B) -> C) -> Self {
+        let tx = TxEnv {
+            transact_to: TransactTo::create(),
+            data: init_code.into(),
+            ..Default::default()
+        };
+        Self {
+            tx,
+            salt: None,
+            factory: None,
+            client,
+            instance,
+            _m: PhantomData,
+        }
+    }
+
+    /// Sets the value of ether sent to the constructor
+    pub fn value<T: Into<U256>>(mut self, value: T) -> Self {
+        self.tx.value = value.into().into();
+        self
+    }
+
+    /// Sets the gas limit of the deployment
+    pub fn gas<T: Into<U256>>(mut self, gas: T) -> Self {
+        self.tx.gas_limit = saturating_u64(gas.into());
+        self
+    }
+
+    /// Deploys with CREATE2 and `salt`, so the address only depends on the
+    /// deployer, the salt and the init code
+    pub fn salt<T: Into<H256>>(mut self, salt: T) -> Self {
+        self.salt = Some(salt.into());
+        self
+    }
+
+    /// Deploys through a CREATE2 factory instead of from the caller, like the
+    /// deterministic deployment proxy at [`DETERMINISTIC_DEPLOYER`], which clients
+    /// install with `ClientBuilder::create2_deployer`. The factory is sent the salt
+    /// followed by the init code, and returns the deployed address.
+    ///
+    /// [`DETERMINISTIC_DEPLOYER`]: revmup_client::DETERMINISTIC_DEPLOYER
+    pub fn factory(mut self, factory: Address) -> Self {
+        self.factory = Some(factory);
+        self
+    }
+
+    /// The address the contract will be deployed at by `caller`, known for
+    /// deployments with a salt
+    pub fn address(&self, caller: Address) -> Option<Address> {
+        let salt = self.salt?;
+        let deployer = self.factory.unwrap_or(caller);
+        Some(create2_address(deployer, salt, &self.tx.data))
+    }
+}
+
+impl<B, R, C> ContractDeployer<B, R, C>
+where
+    B: Borrow<R>,
+    R: RevmClient,
+{
+    /// Deploy the contract from `caller`, returning the bound contract and the
+    /// deployment's receipt
+    pub fn send(self, caller: Address) -> eyre::Result<(C, TransactionReceipt)> {
+        let mut t = self.tx;
+        t.caller = caller.into();
+        let client = self.client.borrow();
+        let (address, receipt) = match (self.salt, self.factory) {
+            (Some(salt), Some(factory)) => {
+                let mut data = salt.as_bytes().to_vec();
+                data.extend_from_slice(&t.data);
+                t.transact_to = TransactTo::Call(factory.into());
+                t.data = data.into();
+                let (output, receipt) = client.send_transaction_with_receipt(t)?;
+                if output.len() != 20 {
+                    eyre::bail!("factory {factory:?} did not return the deployed address");
+                }
+                (Address::from_slice(&output), receipt)
+            }
+            (Some(salt), None) => {
+                let salt = U256::from_big_endian(salt.as_bytes());
+                t.transact_to = TransactTo::Create(CreateScheme::Create2 { salt: salt.into() });
+                client.deploy_with_receipt(t)?
+            }
+            (None, Some(_)) => eyre::bail!("deploying through a factory needs a salt"),
+            (None, None) => client.deploy_with_receipt(t)?,
+        };
+        Ok(((self.instance)(address, self.client), receipt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::Contract;
+    use ethers_core::{abi::parse_abi, utils::parse_ether};
+    use revmup_client::BasicClient;
+    use std::sync::Arc;
+
+    use super::*;
+
+    // deploys a contract returning its caller
+    const INIT_CODE: &str = "0x6009600c60003960096000f33360005260206000f3";
+
+    fn whoami(address: Address, client: Arc<BasicClient>) -> Contract<BasicClient> {
+        let abi = parse_abi(&["function whoami() view returns (address)"]).unwrap();
+        Contract::new(address, abi, client)
+    }
+
+    #[test]
+    fn deploys_and_binds_the_contract() {
+        let client = Arc::new(BasicClient::new());
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let init_code = hex::decode(&INIT_CODE[2..]).unwrap();
+
+        let deployer = ContractDeployment::new(client.clone(), init_code, whoami)
+            .value(5)
+            .gas(200_000)
+            .salt(H256::repeat_byte(1));
+        let expected = deployer.address(alice).unwrap();
+        let (contract, receipt) = deployer.send(alice).unwrap();
+
+        assert_eq!(contract.address(), expected);
+        assert_eq!(receipt.contract_address, Some(expected));
+        assert_eq!(receipt.from, alice);
+        assert_eq!(receipt.status, Some(1.into()));
+        let tx = client.get_transaction(receipt.transaction_hash).unwrap();
+        assert_eq!(tx.gas, 200_000.into());
+        assert_eq!(client.get_balance(expected), 5.into());
+
+        let caller: Address = contract
+            .method::<_, Address>("whoami", ())
+            .unwrap()
+            .from(alice)
+            .call()
+            .unwrap();
+        assert_eq!(caller, alice);
+    }
+}
@@ -0,0 +1,257 @@
+use ethers_contract::{EthLogDecode, LogMeta};
+use ethers_core::{
+    abi::RawLog,
+    types::{Address, BlockNumber, Filter, Log, Topic, ValueOrArray, H256},
+};
+use std::{borrow::Borrow, fmt::Debug, marker::PhantomData};
+
+use revmup_client::{RevmClient, SubscriptionId};
+
+pub type ContractEvent<R, D> = Event<std::sync::Arc<R>, R, D>;
+
+#[derive(Debug)]
+#[must_use = "event filters do nothing unless you `query` them"]
+/// Helper for querying the logs of a contract event from the client's history
+pub struct Event<B, R, D> {
+    /// The log filter
+    pub filter: Filter,
+    pub(crate) client: B,
+    pub(crate) datatype: PhantomData<D>,
+    pub(crate) _m: PhantomData<R>,
+}
+
+impl<B, R, D> Event<B, R, D>
+where
+    B: Borrow<R>,
+    R: RevmClient,
+    D: EthLogDecode,
+{
+    /// Sets the filter's `from` block
+    pub fn from_block<T: Into<BlockNumber>>(mut self, block: T) -> Self {
+        self.filter = self.filter.from_block(block);
+        self
+    }
+
+    /// Sets the filter's `to` block
+    pub fn to_block<T: Into<BlockNumber>>(mut self, block: T) -> Self {
+        self.filter = self.filter.to_block(block);
+        self
+    }
+
+    /// Restricts the filter to a single block by its hash
+    pub fn at_block_hash<T: Into<H256>>(mut self, hash: T) -> Self {
+        self.filter = self.filter.at_block_hash(hash);
+        self
+    }
+
+    /// Sets the filter's address
+    pub fn address<T: Into<ValueOrArray<Address>>>(mut self, address: T) -> Self {
+        self.filter = self.filter.address(address);
+        self
+    }
+
+    /// Sets the filter's 0th topic (typically the event name for non-anonymous events)
+    pub fn topic0<T: Into<Topic>>(mut self, topic: T) -> Self {
+        self.filter.topics[0] = Some(topic.into());
+        self
+    }
+
+    /// Sets the filter's 1st indexed topic
+    pub fn topic1<T: Into<Topic>>(mut self, topic: T) -> Self {
+        self.filter.topics[1] = Some(topic.into());
+        self
+    }
+
+    /// Sets the filter's 2nd indexed topic
+    pub fn topic2<T: Into<Topic>>(mut self, topic: T) -> Self {
+        self.filter.topics[2] = Some(topic.into());
+        self
+    }
+
+    /// Sets the filter's 3rd indexed topic
+    pub fn topic3<T: Into<Topic>>(mut self, topic: T) -> Self {
+        self.filter.topics[3] = Some(topic.into());
+        self
+    }
+
+    /// Returns the decoded events matching the filter, along with the metadata
+    /// of the log each one was decoded from. Logs that fail to decode are skipped.
+    pub fn query(&self) -> eyre::Result<Vec<(D, LogMeta)>> {
+        let logs = self.client.borrow().get_logs(&self.filter)?;
+        Ok(logs.iter().filter_map(decode_log).collect())
+    }
+
+    /// Calls `callback` with each decoded event matching the filter, along with
+    /// the metadata of its log, as transactions are committed. The filter's block
+    /// range is ignored. Logs that fail to decode are skipped.
+    pub fn subscribe<F>(&self, callback: F) -> eyre::Result<SubscriptionId>
+    where
+        D: 'static,
+        F: Fn(D, LogMeta) + Send + Sync + 'static,
+    {
+        self.client.borrow().subscribe_logs(
+            self.filter.clone(),
+            Box::new(move |log| {
+                if let Some((event, meta)) = decode_log(log) {
+                    callback(event, meta);
+                }
+            }),
+        )
+    }
+}
+
+/// Decode a log into the event `D` and the log's metadata
+fn decode_log<D: EthLogDecode>(log: &Log) -> Option<(D, LogMeta)> {
+    let raw = RawLog {
+        topics: log.topics.clone(),
+        data: log.data.to_vec(),
+    };
+    D::decode_log(&raw)
+        .ok()
+        .map(|event| (event, LogMeta::from(log)))
+}
+
+impl<B, R, D> Clone for Event<B, R, D>
+where
+    B: Clone,
+{
+    fn clone(&self) -> Self {
+        Event {
+            filter: self.filter.clone(),
+            client: self.client.clone(),
+            datatype: self.datatype,
+            _m: self._m,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::Contract;
+    use ethers_contract::EthEvent;
+    use ethers_core::{
+        abi::{encode, parse_abi, Token},
+        types::U256,
+        utils::{keccak256, parse_ether},
+    };
+    use revm::primitives::{TransactTo, TxEnv};
+    use revmup_client::{generate_random_account, BasicClient};
+    use std::{
+        collections::HashMap,
+        sync::{mpsc, Arc},
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, EthEvent)]
+    #[ethevent(name = "Poked", abi = "Poked(uint256)")]
+    struct Poked {
+        value: U256,
+    }
+
+    struct Setup {
+        client: Arc<BasicClient>,
+        alice: Address,
+        emitter: Contract<BasicClient>,
+        reverter: Contract<BasicClient>,
+    }
+
+    /// Etch a contract emitting `Poked` with the value it's called with, and
+    /// one that emits it then reverts
+    fn setup() -> Setup {
+        let client = Arc::new(BasicClient::new());
+        let alice = client
+            .create_account_with_balance(parse_ether(1u8).unwrap())
+            .unwrap();
+        let signature = hex::encode(keccak256("Poked(uint256)"));
+        let emit = format!("6000356000527f{signature}60206000a1");
+        let contract = |code: String| {
+            let address = generate_random_account();
+            client
+                .etch(address, code.parse().unwrap(), HashMap::new())
+                .unwrap();
+            let abi = parse_abi(&["event Poked(uint256 value)"]).unwrap();
+            Contract::new(address, abi, client.clone())
+        };
+        let emitter = contract(format!("0x{emit}00"));
+        let reverter = contract(format!("0x{emit}60006000fd"));
+        Setup {
+            client,
+            alice,
+            emitter,
+            reverter,
+        }
+    }
+
+    impl Setup {
+        /// Poke `contract` with `value` in a block of its own
+        fn poke(&self, contract: &Contract<BasicClient>, value: u64) -> eyre::Result<()> {
+            self.client.send_transaction(TxEnv {
+                caller: self.alice.into(),
+                transact_to: TransactTo::Call(contract.address().into()),
+                data: encode(&[Token::Uint(value.into())]).into(),
+                ..Default::default()
+            })?;
+            Ok(())
+        }
+    }
+
+    fn values(events: Vec<(Poked, LogMeta)>) -> Vec<(u64, u64)> {
+        events
+            .into_iter()
+            .map(|(event, meta)| (event.value.as_u64(), meta.block_number.as_u64()))
+            .collect()
+    }
+
+    #[test]
+    fn queries_events() {
+        let setup = setup();
+        setup.poke(&setup.emitter, 1).unwrap();
+        assert!(setup.poke(&setup.reverter, 2).is_err());
+        setup.poke(&setup.emitter, 3).unwrap();
+
+        let events = setup.emitter.event::<Poked>().from_block(0).query();
+        let events = events.unwrap();
+        assert_eq!(events[0].1.address, setup.emitter.address());
+        assert_eq!(values(events), [(1, 1), (3, 3)]);
+        // like `eth_getLogs`, the range defaults to the latest block
+        let latest = setup.emitter.event::<Poked>().query().unwrap();
+        assert_eq!(values(latest), [(3, 3)]);
+        // the reverted transaction was mined, without its logs
+        assert_eq!(setup.client.get_block(2).unwrap().transactions.len(), 1);
+        let reverted = setup.reverter.event::<Poked>().from_block(0).query();
+        assert!(reverted.unwrap().is_empty());
+
+        let ranged = setup.emitter.event::<Poked>().from_block(2).to_block(3);
+        assert_eq!(values(ranged.query().unwrap()), [(3, 3)]);
+        let ranged = setup.emitter.event::<Poked>().from_block(0).to_block(2);
+        assert_eq!(values(ranged.query().unwrap()), [(1, 1)]);
+        let hash = setup.client.get_block(3).unwrap().hash.unwrap();
+        let at_hash = setup.emitter.event::<Poked>().at_block_hash(hash);
+        assert_eq!(values(at_hash.query().unwrap()), [(3, 3)]);
+    }
+
+    #[test]
+    fn delivers_subscribed_events() {
+        let setup = setup();
+        let (sender, received) = mpsc::channel();
+        for contract in [&setup.emitter, &setup.reverter] {
+            let sender = sender.clone();
+            contract
+                .event::<Poked>()
+                .subscribe(move |event, meta| {
+                    sender
+                        .send((event.value.as_u64(), meta.block_number.as_u64()))
+                        .unwrap();
+                })
+                .unwrap();
+        }
+        drop(sender);
+
+        setup.poke(&setup.emitter, 1).unwrap();
+        assert!(setup.poke(&setup.reverter, 2).is_err());
+        setup.poke(&setup.emitter, 3).unwrap();
+        drop(setup);
+        assert_eq!(received.iter().collect::<Vec<_>>(), [(1, 1), (3, 3)]);
+    }
+}
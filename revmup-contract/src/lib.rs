@@ -1,2 +1,7 @@
 pub mod call;
 pub mod contract;
+pub mod deploy;
+pub mod event;
+
+// re-exported for the generated bindings
+pub use eyre;